@@ -0,0 +1,68 @@
+#ifndef DOTA_ODDS_CALC_H
+#define DOTA_ODDS_CALC_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What a function returned. Anything but `Ok` comes with a message from `doc_last_error`.
+typedef enum DocStatus {
+  DOC_STATUS_OK = 0,
+  // A pointer argument was null
+  DOC_STATUS_NULL_POINTER = 1,
+  // A string argument wasn't valid UTF-8
+  DOC_STATUS_INVALID_UTF8 = 2,
+  // Treasure openings start at 1
+  DOC_STATUS_INVALID_TREASURE_OPENING = 3,
+  DOC_STATUS_UNKNOWN_RARITY = 4,
+  DOC_STATUS_UNKNOWN_TREASURE = 5,
+  // An odds file that couldn't be read
+  DOC_STATUS_IO = 6,
+  // An odds file that couldn't be parsed, or that has a table that can't be calculated with
+  DOC_STATUS_INVALID_ODDS = 7,
+  // A bug in the calculator. Please report it!
+  DOC_STATUS_PANIC = 8,
+} DocStatus;
+
+// Calculate the expected number of boxes, starting with `treasure_opening`, needed to get an item of `rarity`
+//
+// # Safety
+// `rarity` must be a nul-terminated string, and `out` must be valid for writes
+enum DocStatus doc_expected_value(const char *rarity,
+                                  size_t treasure_opening,
+                                  double *out);
+
+// Calculate the probability of getting an item of `rarity` within `boxes` boxes, starting with `treasure_opening`
+//
+// # Safety
+// `rarity` must be a nul-terminated string, and `out` must be valid for writes
+enum DocStatus doc_probability(const char *rarity,
+                               size_t treasure_opening,
+                               size_t boxes,
+                               double *out);
+
+// Load the odds tables of an odds file (TOML, or JSON if the path ends in .json). Tables are added to the treasure
+// the file names, or to the built-in one, replacing any tables with the same names.
+//
+// # Safety
+// `path` must be a nul-terminated string
+enum DocStatus doc_load_odds_file(const char *path);
+
+// Calculate with the odds tables of `treasure` from now on. It's `standard`, the built-in treasure, to start with.
+//
+// # Safety
+// `treasure` must be a nul-terminated string
+enum DocStatus doc_set_treasure(const char *treasure);
+
+// Go back to the built-in odds tables, forgetting any loaded odds files
+enum DocStatus doc_reset_odds(void);
+
+// The message of the last call on this thread that failed, or null if it succeeded. The string belongs to the
+// library and is only valid until the next call on this thread.
+const char *doc_last_error(void);
+
+#endif  /* DOTA_ODDS_CALC_H */
@@ -0,0 +1,305 @@
+use serde::Serialize;
+
+use num_traits::pow;
+
+use crate::{number::Number, odds::OddsTable, Error};
+
+/// The expected number of boxes, starting with `treasure_opening`, needed to get the item
+pub fn expected_value<N: Number>(table: &OddsTable, treasure_opening: usize) -> Result<N, Error> {
+    table.check(treasure_opening)?;
+
+    // The probability that we make it to this point
+    let mut cum_prob = N::one();
+    // Expected value
+    let mut exp = N::zero();
+    for (i, p) in table.odds.iter().enumerate().skip(treasure_opening - 1) {
+        // The probability of the ith chest being the next one we open is the probability of getting to the ith chest
+        // times the probability of opening that chest (1 / p)
+        let p = N::one() / N::from_f64(*p);
+        exp = exp + N::from_usize((i + 1) - (treasure_opening - 1)) * cum_prob.clone() * p.clone();
+
+        // Then the probability we make it to the next chest is the probability we made it to this chest times the
+        // probability we didn't open this chest
+        cum_prob = cum_prob * (N::one() - p);
+    }
+    // Past the end of the table the odds stay the same, so the number of extra boxes is geometrically distributed
+    let tail_odds = N::from_f64(table.tail_odds());
+    exp = exp
+        + if treasure_opening <= table.odds.len() {
+            cum_prob * (tail_odds + N::from_usize(table.odds.len() - treasure_opening + 1))
+        } else {
+            tail_odds
+        };
+
+    Ok(exp)
+}
+
+/// The probability of getting the item within `num_boxes` boxes, starting with `treasure_opening`
+pub fn probability<N: Number>(
+    table: &OddsTable,
+    treasure_opening: usize,
+    num_boxes: usize,
+) -> Result<N, Error> {
+    Ok(first_drop_probabilities(table, treasure_opening)?
+        .take(num_boxes)
+        .fold(N::zero(), |sum, prob| sum + prob))
+}
+
+/// The probability that each box, starting with `treasure_opening`, is the first one to have the item in it
+pub fn first_drop_probabilities<N: Number>(
+    table: &OddsTable,
+    treasure_opening: usize,
+) -> Result<impl Iterator<Item = N> + '_, Error> {
+    table.check(treasure_opening)?;
+
+    Ok(table
+        .odds_from(treasure_opening)
+        .scan(N::one(), |cum_prob, p| {
+            // The probability of the ith chest being the next one we open is the probability of getting to the ith
+            // chest times the probability of opening that chest (1 / p)
+            let p = N::one() / N::from_f64(p);
+            let prob = cum_prob.clone() * p.clone();
+
+            // Then the probability we make it to the next chest is the probability we made it to this chest times the
+            // probability we didn't open this chest
+            *cum_prob = cum_prob.clone() * (N::one() - p);
+
+            Some(prob)
+        }))
+}
+
+/// One point of the distribution of the number of boxes needed to get the item
+#[derive(Serialize, Clone, Debug)]
+pub struct DistributionPoint<N = f64> {
+    /// The number of boxes opened, counting from the current treasure opening
+    pub boxes: usize,
+    /// The treasure opening of the last box opened
+    pub opening: usize,
+    /// The probability that the item is in exactly the last box opened
+    pub exactly: N,
+    /// The probability that the item is in one of the boxes opened
+    pub by: N,
+}
+
+/// The distribution of the number of boxes needed to get the item, from 1 to `num_boxes` boxes
+pub fn distribution<N: Number>(
+    table: &OddsTable,
+    treasure_opening: usize,
+    num_boxes: usize,
+) -> Result<Vec<DistributionPoint<N>>, Error> {
+    Ok(first_drop_probabilities(table, treasure_opening)?
+        .take(num_boxes)
+        .enumerate()
+        .scan(N::zero(), |by, (i, exactly): (usize, N)| {
+            *by = by.clone() + exactly.clone();
+            Some(DistributionPoint {
+                boxes: i + 1,
+                opening: treasure_opening + i,
+                exactly,
+                by: by.clone(),
+            })
+        })
+        .collect())
+}
+
+/// The smallest number of boxes, starting with `treasure_opening`, that gives at least a `level` chance of getting the
+/// item, or `None` if no number of boxes is enough
+pub fn quantile<N: Number>(
+    table: &OddsTable,
+    treasure_opening: usize,
+    level: N,
+) -> Result<Option<usize>, Error> {
+    table.check(treasure_opening)?;
+    if !(N::zero()..=N::one()).contains(&level) {
+        return Err(Error::InvalidConfidenceLevel(level.to_f64()));
+    }
+
+    // The probability that we still haven't gotten the item
+    let mut survival = N::one();
+    let mut boxes = 0;
+    let reached = |survival: &N| N::one() - survival.clone() >= level;
+
+    for p in table.odds.iter().skip(treasure_opening - 1) {
+        if reached(&survival) {
+            return Ok(Some(boxes));
+        }
+        survival = survival * (N::one() - N::one() / N::from_f64(*p));
+        boxes += 1;
+    }
+    if reached(&survival) {
+        return Ok(Some(boxes));
+    }
+
+    // Past the end of the table the odds stay the same, so solve survival * (1 - p)^n <= 1 - level for n instead of
+    // opening boxes one at a time
+    let p = N::one() / N::from_f64(table.tail_odds());
+    if p >= N::one() {
+        return Ok(Some(boxes + 1));
+    }
+    if level >= N::one() {
+        return Ok(None);
+    }
+    let mut n = (((1. - level.to_f64()) / survival.to_f64()).ln() / (1. - p.to_f64()).ln())
+        .ceil()
+        .max(1.) as usize;
+    // Make up for any rounding in the logarithms
+    let after = |n: usize| survival.clone() * pow(N::one() - p.clone(), n);
+    while n > 1 && reached(&after(n - 1)) {
+        n -= 1;
+    }
+    while !reached(&after(n)) {
+        n += 1;
+    }
+
+    Ok(Some(boxes + n))
+}
+
+/// How spread out the number of boxes needed to get the item is
+#[derive(Serialize, Clone, Debug)]
+pub struct Summary {
+    pub expected_value: f64,
+    pub variance: f64,
+    pub std_dev: f64,
+    pub median: usize,
+    pub percentile_10: usize,
+    pub percentile_90: usize,
+}
+
+pub fn summary(table: &OddsTable, treasure_opening: usize) -> Result<Summary, Error> {
+    table.check(treasure_opening)?;
+
+    // The probability that we make it to this point
+    let mut cum_prob = 1.;
+    // The first and second moments of the number of boxes needed
+    let mut exp = 0.;
+    let mut exp_sq = 0.;
+    let mut boxes = 0.;
+    for p in table.odds.iter().skip(treasure_opening - 1) {
+        let p = 1. / p;
+        boxes += 1.;
+        exp += boxes * cum_prob * p;
+        exp_sq += boxes * boxes * cum_prob * p;
+        cum_prob *= 1. - p;
+    }
+
+    // Past the end of the table we need `boxes` plus a geometrically distributed number of extra boxes, whose mean is
+    // 1 / p and whose second moment is (2 - p) / p^2
+    let p = 1. / table.tail_odds();
+    exp += cum_prob * (boxes + 1. / p);
+    exp_sq += cum_prob * (boxes * boxes + 2. * boxes / p + (2. - p) / (p * p));
+
+    let variance = (exp_sq - exp * exp).max(0.);
+    // Every level below 1 can be reached eventually
+    let percentile = |level| Ok::<_, Error>(quantile(table, treasure_opening, level)?.unwrap());
+    Ok(Summary {
+        expected_value: exp,
+        variance,
+        std_dev: variance.sqrt(),
+        median: percentile(0.5)?,
+        percentile_10: percentile(0.1)?,
+        percentile_90: percentile(0.9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{number::Exact, Rarity, Tail};
+
+    /// A coin flip at every opening, so the number of boxes needed is geometric
+    fn coin() -> OddsTable {
+        OddsTable {
+            name: "coin".to_owned(),
+            odds: vec![2.],
+            tail: Tail::RepeatLast,
+        }
+    }
+
+    #[test]
+    fn quantiles_of_a_geometric_distribution() {
+        let coin = coin();
+        for (level, boxes) in [
+            (0., Some(0)),
+            (0.5, Some(1)),
+            (0.75, Some(2)),
+            (0.9, Some(4)),
+        ] {
+            assert_eq!(quantile(&coin, 1, level).unwrap(), boxes, "{level}");
+        }
+        // No number of boxes is certain to be enough
+        assert_eq!(quantile(&coin, 1, 1.).unwrap(), None);
+        assert!(quantile(&coin, 1, 1.5).is_err());
+    }
+
+    #[test]
+    fn quantiles_are_the_fewest_boxes_with_that_probability() {
+        for rarity in Rarity::ALL {
+            let table = rarity.table();
+            // Within the table and well past its end
+            for level in [0.5, 0.9, 0.999_999] {
+                let boxes = quantile(&table, 3, level).unwrap().unwrap();
+                assert!(probability::<f64>(&table, 3, boxes).unwrap() >= level);
+                assert!(probability::<f64>(&table, 3, boxes - 1).unwrap() < level);
+            }
+        }
+    }
+
+    #[test]
+    fn a_sure_opening_is_certain() {
+        let table = OddsTable {
+            name: "sure".to_owned(),
+            odds: vec![2., 1.],
+            tail: Tail::RepeatLast,
+        };
+        assert_eq!(quantile(&table, 1, 1.).unwrap(), Some(2));
+        assert_eq!(quantile(&table, 2, 1.).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn exact_quantiles_agree_with_floats() {
+        let table = Rarity::UltraRare.table();
+        for level in [0.1, 0.5, 0.9, 0.999] {
+            assert_eq!(
+                quantile(&table, 7, Exact::from_f64(level)).unwrap(),
+                quantile(&table, 7, level).unwrap(),
+                "{level}"
+            );
+        }
+        // An exact half is reached by exactly 1 coin flip
+        let half = Exact::new(1.into(), 2.into());
+        assert_eq!(quantile(&coin(), 1, half).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn exact_distribution_ends_at_the_probability() {
+        let table = Rarity::Rare.table();
+        let points = distribution::<Exact>(&table, 2, 40).unwrap();
+        assert_eq!(points.len(), 40);
+        assert_eq!(points[39].by, probability::<Exact>(&table, 2, 40).unwrap());
+        let floats = distribution::<f64>(&table, 2, 40).unwrap();
+        assert!((points[20].exactly.to_f64() - floats[20].exactly).abs() < 1e-15);
+    }
+
+    #[test]
+    fn summary_of_a_geometric_distribution() {
+        let summary = summary(&coin(), 1).unwrap();
+        // A mean of 1 / p and a variance of (1 - p) / p^2
+        assert_eq!(summary.expected_value, 2.);
+        assert_eq!(summary.variance, 2.);
+        assert_eq!(
+            (summary.percentile_10, summary.median, summary.percentile_90),
+            (1, 1, 4)
+        );
+    }
+
+    #[test]
+    fn summary_agrees_with_the_expected_value() {
+        for rarity in Rarity::ALL {
+            let table = rarity.table();
+            let summary = summary(&table, 5).unwrap();
+            let expected: f64 = expected_value(&table, 5).unwrap();
+            assert!((summary.expected_value - expected).abs() < 1e-12 * expected);
+            assert_eq!(summary.median, quantile(&table, 5, 0.5).unwrap().unwrap());
+        }
+    }
+}
@@ -0,0 +1,907 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    calc::{distribution, expected_value},
+    cost::Pricing,
+    multi::{self, Goal},
+    odds::OddsTable,
+    treasure::Treasure,
+    Error,
+};
+
+/// The most values a range can have, so that a range like `1..1e13` is an error instead of running out of memory
+pub const MAX_RANGE_VALUES: usize = 10_000;
+
+/// The largest treasure opening or number of boxes a chart can sweep to. Each cell's probability is summed box by box,
+/// so charting huge numbers of boxes takes a long time even with only a few columns.
+pub const MAX_COUNT: usize = 100_000;
+
+/// A quantity a chart can sweep along one of its axes
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quantity {
+    /// The treasure opening the boxes start at
+    Opening,
+    /// The number of boxes opened
+    Boxes,
+    /// The money spent on boxes, bought as cheaply as possible
+    Budget,
+    /// The rarity (odds table) of the item
+    Rarity,
+}
+
+impl Quantity {
+    pub const ALL: [Quantity; 4] = [
+        Quantity::Opening,
+        Quantity::Boxes,
+        Quantity::Budget,
+        Quantity::Rarity,
+    ];
+
+    /// The name this quantity goes by on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quantity::Opening => "opening",
+            Quantity::Boxes => "boxes",
+            Quantity::Budget => "budget",
+            Quantity::Rarity => "rarity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Quantity> {
+        Quantity::ALL
+            .into_iter()
+            .find(|quantity| quantity.name() == name)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How the values of a range are spaced
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spacing {
+    /// Every `step` from the start
+    Step(f64),
+    /// This many values, evenly spaced on a log scale so that each is the same multiple of the one before
+    Log(usize),
+}
+
+/// The values from `start` to `end`, written as `<start>..<end>` and optionally followed by `:<step>` (1 by default)
+/// or `:log<points>`. A single number is a range of just that number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: f64,
+    pub end: f64,
+    pub spacing: Spacing,
+}
+
+impl FromStr for Range {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::InvalidRange {
+            range: s.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let syntax =
+            || invalid("expected <start>..<end>, optionally followed by :<step> or :log<points>");
+        let number = |n: &str| {
+            n.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or_else(syntax)
+        };
+
+        let (bounds, spacing) = match s.split_once(':') {
+            Some((bounds, spacing)) => (bounds, Some(spacing.trim())),
+            None => (s, None),
+        };
+        let (start, end) = match bounds.split_once("..") {
+            Some((start, end)) => (number(start)?, number(end)?),
+            None => (number(bounds)?, number(bounds)?),
+        };
+        let spacing = match spacing.map(|spacing| (spacing, spacing.strip_prefix("log"))) {
+            None => Spacing::Step(1.),
+            Some((_, Some(points))) => Spacing::Log(points.parse().map_err(|_| syntax())?),
+            Some((step, None)) => Spacing::Step(number(step)?),
+        };
+
+        if start > end {
+            return Err(invalid("the start is past the end"));
+        }
+        match spacing {
+            Spacing::Step(step) if step <= 0. => Err(invalid("the step must be greater than 0")),
+            Spacing::Log(points) if points < 2 => {
+                Err(invalid("log-spaced ranges need at least 2 points"))
+            }
+            Spacing::Log(_) if start <= 0. => Err(invalid("log-spaced ranges must start above 0")),
+            _ => {
+                let range = Range {
+                    start,
+                    end,
+                    spacing,
+                };
+                // Counted before any values are made, so that huge ranges never get allocated
+                match range.steps() < MAX_RANGE_VALUES as f64 {
+                    true => Ok(range),
+                    false => Err(invalid(&format!(
+                        "ranges can have at most {MAX_RANGE_VALUES} values"
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)?;
+        match self.spacing {
+            Spacing::Step(step) => write!(f, ":{step}"),
+            Spacing::Log(points) => write!(f, ":log{points}"),
+        }
+    }
+}
+
+impl Range {
+    /// The number of steps from `start` to the last value, which is one less than the number of values
+    fn steps(&self) -> f64 {
+        match self.spacing {
+            // A little slack so that e.g. 0..1:0.1 still ends at 1 despite rounding
+            Spacing::Step(step) => ((self.end - self.start) / step + 1e-9).floor(),
+            Spacing::Log(points) => points as f64 - 1.,
+        }
+    }
+
+    /// The number of values of the range
+    fn len(&self) -> usize {
+        self.steps() as usize + 1
+    }
+
+    /// Every value of the range, from `start` up to at most `end`
+    pub fn values(&self) -> Vec<f64> {
+        match self.spacing {
+            Spacing::Step(step) => (0..self.len())
+                .map(|i| self.start + i as f64 * step)
+                .collect(),
+            Spacing::Log(points) => (0..points)
+                .map(|i| {
+                    let t = i as f64 / (points - 1) as f64;
+                    self.start * (self.end / self.start).powf(t)
+                })
+                .collect(),
+        }
+    }
+
+    /// The values as whole numbers, for treasure openings and numbers of boxes. Log-spaced values are rounded to the
+    /// nearest whole number, and kept once when several round to the same one.
+    pub fn counts(&self) -> Result<Vec<usize>, Error> {
+        let invalid = |reason: String| Error::InvalidRange {
+            range: self.to_string(),
+            reason,
+        };
+        let whole = |x: f64| x >= 0. && x.fract() == 0.;
+        let whole_step = match self.spacing {
+            Spacing::Step(step) => whole(step),
+            Spacing::Log(_) => true,
+        };
+        if !(whole(self.start) && whole(self.end) && whole_step) {
+            return Err(invalid(
+                "openings and boxes must be whole numbers of 0 or more".to_owned(),
+            ));
+        }
+        if self.end > MAX_COUNT as f64 {
+            return Err(invalid(format!(
+                "openings and boxes can be at most {MAX_COUNT}"
+            )));
+        }
+
+        let mut counts: Vec<usize> = self
+            .values()
+            .into_iter()
+            .map(|x| x.round() as usize)
+            .collect();
+        counts.dedup();
+        Ok(counts)
+    }
+}
+
+/// The values a chart sweeps along one of its axes
+#[derive(Clone, Debug, PartialEq)]
+pub enum Axis {
+    Openings(Vec<usize>),
+    Boxes(Vec<usize>),
+    Budgets(Vec<f64>),
+    /// The names of rarities of the charted treasure
+    Rarities(Vec<String>),
+}
+
+impl Axis {
+    /// Parse an axis written as `<quantity>=<values>`, where the values are a `Range` for opening, boxes and budget
+    /// and a comma-separated list for rarity (e.g. `boxes=10..200:10` or `rarity=rare,ultra-rare`). A plain number N
+    /// is short for `default` from 1 to N.
+    pub fn parse(s: &str, default: Quantity) -> Result<Axis, Error> {
+        let invalid = || Error::InvalidAxis(s.to_owned());
+        let (quantity, values) = match s.split_once('=') {
+            Some((quantity, values)) => (
+                Quantity::from_name(quantity.trim()).ok_or_else(invalid)?,
+                values.trim().to_owned(),
+            ),
+            None => {
+                let max: usize = s.trim().parse().map_err(|_| invalid())?;
+                (default, format!("1..{max}"))
+            }
+        };
+
+        if quantity == Quantity::Rarity {
+            let rarities: Vec<String> = values
+                .split(',')
+                .map(str::trim)
+                .filter(|rarity| !rarity.is_empty())
+                .map(str::to_owned)
+                .collect();
+            if rarities.is_empty() {
+                return Err(invalid());
+            }
+            return Ok(Axis::Rarities(rarities));
+        }
+
+        let range: Range = values.parse()?;
+        match quantity {
+            Quantity::Opening => {
+                let openings = range.counts()?;
+                if openings.first() == Some(&0) {
+                    return Err(Error::InvalidRange {
+                        range: values,
+                        reason: "treasure openings start at 1".to_owned(),
+                    });
+                }
+                Ok(Axis::Openings(openings))
+            }
+            Quantity::Boxes => Ok(Axis::Boxes(range.counts()?)),
+            Quantity::Budget => Ok(Axis::Budgets(range.values())),
+            Quantity::Rarity => unreachable!("rarities aren't ranges"),
+        }
+    }
+
+    pub fn quantity(&self) -> Quantity {
+        match self {
+            Axis::Openings(_) => Quantity::Opening,
+            Axis::Boxes(_) => Quantity::Boxes,
+            Axis::Budgets(_) => Quantity::Budget,
+            Axis::Rarities(_) => Quantity::Rarity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Axis::Openings(values) | Axis::Boxes(values) => values.len(),
+            Axis::Budgets(values) => values.len(),
+            Axis::Rarities(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `i`th value of the axis
+    pub fn value(&self, i: usize) -> AxisValue {
+        match self {
+            Axis::Openings(values) | Axis::Boxes(values) => AxisValue::Count(values[i]),
+            Axis::Budgets(values) => AxisValue::Amount(values[i]),
+            Axis::Rarities(values) => AxisValue::Name(values[i].clone()),
+        }
+    }
+}
+
+/// One value of a chart's axis: a treasure opening or number of boxes, a budget, or the name of a rarity
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum AxisValue {
+    Count(usize),
+    Amount(f64),
+    Name(String),
+}
+
+impl AxisValue {
+    /// The value as a number, for plotting. Rarities aren't numbers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AxisValue::Count(count) => Some(*count as f64),
+            AxisValue::Amount(amount) => Some(*amount),
+            AxisValue::Name(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for AxisValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxisValue::Count(count) => write!(f, "{count}"),
+            AxisValue::Amount(amount) => write!(f, "{amount}"),
+            AxisValue::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// What the cells of a chart hold
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Measure {
+    /// The probability of getting the item within the cell's boxes, for charts with boxes or budget on an axis
+    Probability,
+    /// The expected number of boxes needed to get the item, for charts without boxes or budget on an axis
+    ExpectedValue,
+}
+
+/// The expected values or probabilities of every combination of the values along a chart's two axes
+#[derive(Serialize, Clone, Debug)]
+pub struct Chart {
+    pub row_quantity: Quantity,
+    pub column_quantity: Quantity,
+    pub measure: Measure,
+    /// The rarity of every cell, unless rarity is on an axis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<String>,
+    /// The treasure opening every cell starts at, unless opening is on an axis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treasure_opening: Option<usize>,
+    pub columns: Vec<ChartLine>,
+    pub rows: Vec<ChartLine>,
+    /// The cell of each row and column, as `cells[row][column]`
+    pub cells: Vec<Vec<f64>>,
+}
+
+/// One row or column of a chart
+#[derive(Serialize, Clone, Debug)]
+pub struct ChartLine {
+    /// The treasure opening, number of boxes, budget or rarity of the row or column
+    pub value: AxisValue,
+    /// The number of boxes a budget buys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boxes: Option<usize>,
+    /// The cheapest price of the boxes of a number of boxes or a budget, if a price was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// The expected number of boxes needed to get the item, for treasure openings and rarities charted against boxes
+    /// or budget
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_value: Option<f64>,
+    /// The expected amount spent getting the item, if a price was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_spend: Option<f64>,
+}
+
+/// What a chart sweeps along its rows and its columns
+#[derive(Clone, Debug)]
+pub struct ChartSpec {
+    pub rows: Axis,
+    pub columns: Axis,
+    /// The rarity to chart, unless rarity is on an axis
+    pub rarity: Option<String>,
+    /// The treasure opening the boxes start at, unless opening is on an axis
+    pub treasure_opening: usize,
+}
+
+impl ChartSpec {
+    /// Every starting treasure from 1 to `max_treasures` against every number of boxes from 1 to `max_boxes`
+    pub fn up_to(rarity: &str, max_treasures: usize, max_boxes: usize) -> Self {
+        ChartSpec {
+            rows: Axis::Openings((1..=max_treasures).collect()),
+            columns: Axis::Boxes((1..=max_boxes).collect()),
+            rarity: Some(rarity.to_owned()),
+            treasure_opening: 1,
+        }
+    }
+}
+
+/// What one row or column of a chart sets. Budgets set the number of boxes they buy.
+#[derive(Clone, Copy, Debug)]
+enum Setting<'a> {
+    Opening(usize),
+    Boxes(usize),
+    Rarity(&'a str),
+}
+
+/// The rarity, starting treasure and number of boxes of a cell
+#[derive(Clone, Copy, Debug)]
+struct Point<'a> {
+    rarity: &'a str,
+    treasure_opening: usize,
+    boxes: usize,
+}
+
+impl<'a> Point<'a> {
+    fn with(self, setting: Setting<'a>) -> Self {
+        match setting {
+            Setting::Opening(treasure_opening) => Point {
+                treasure_opening,
+                ..self
+            },
+            Setting::Boxes(boxes) => Point { boxes, ..self },
+            Setting::Rarity(rarity) => Point { rarity, ..self },
+        }
+    }
+}
+
+fn settings<'a>(axis: &'a Axis, pricing: Option<&Pricing>) -> Result<Vec<Setting<'a>>, Error> {
+    Ok(match axis {
+        Axis::Openings(openings) => openings.iter().map(|&o| Setting::Opening(o)).collect(),
+        Axis::Boxes(boxes) => boxes.iter().map(|&b| Setting::Boxes(b)).collect(),
+        Axis::Budgets(budgets) => {
+            let pricing = pricing.ok_or_else(|| {
+                Error::InvalidChart("budgets can only be charted with a price per box".to_owned())
+            })?;
+            let too_many =
+                || Error::InvalidChart(format!("budgets can buy at most {MAX_COUNT} boxes"));
+            budgets
+                .iter()
+                .map(|&budget| {
+                    // Checked before the boxes are worked out, since that takes as long as the budget is big
+                    if budget / pricing.price_per_box > MAX_COUNT as f64 {
+                        return Err(too_many());
+                    }
+                    match pricing.boxes_within(budget)? {
+                        boxes if boxes > MAX_COUNT => Err(too_many()),
+                        boxes => Ok(Setting::Boxes(boxes)),
+                    }
+                })
+                .collect::<Result<_, Error>>()?
+        }
+        Axis::Rarities(rarities) => rarities.iter().map(|r| Setting::Rarity(r)).collect(),
+    })
+}
+
+/// What a chart of `rows` against `columns` holds, or why they can't be charted against each other
+fn measure(rows: &Axis, columns: &Axis) -> Result<Measure, Error> {
+    let (rows, columns) = (rows.quantity(), columns.quantity());
+    let boxes = |quantity| matches!(quantity, Quantity::Boxes | Quantity::Budget);
+    if rows == columns {
+        Err(Error::InvalidChart(format!("{rows} is on both axes")))
+    } else if boxes(rows) && boxes(columns) {
+        Err(Error::InvalidChart(
+            "boxes and budget can't be charted against each other, since a budget is a number of boxes"
+                .to_owned(),
+        ))
+    } else if boxes(rows) || boxes(columns) {
+        Ok(Measure::Probability)
+    } else {
+        Ok(Measure::ExpectedValue)
+    }
+}
+
+/// A row or column for each value of `axis`
+fn lines<'a>(
+    axis: &Axis,
+    settings: &[Setting<'a>],
+    line: impl Fn(&Axis, usize, Setting<'a>) -> Result<ChartLine, Error>,
+) -> Result<Vec<ChartLine>, Error> {
+    (settings.iter().enumerate())
+        .map(|(i, &setting)| line(axis, i, setting))
+        .collect()
+}
+
+/// The cell of every row and column
+fn cells<'a>(
+    rows: &[Setting<'a>],
+    columns: &[Setting<'a>],
+    mut cell: impl FnMut(Setting<'a>, Setting<'a>) -> Result<f64, Error>,
+) -> Result<Vec<Vec<f64>>, Error> {
+    rows.iter()
+        .map(|&row| columns.iter().map(|&column| cell(row, column)).collect())
+        .collect()
+}
+
+/// The probability of getting the item within each number of boxes from 0 to `max_boxes`
+fn cdf(table: &OddsTable, treasure_opening: usize, max_boxes: usize) -> Result<Vec<f64>, Error> {
+    let points = distribution(table, treasure_opening, max_boxes)?;
+    Ok(std::iter::once(0.)
+        .chain(points.iter().map(|point| point.by))
+        .collect())
+}
+
+/// Chart `spec.rows` against `spec.columns` for the tables of `treasure`. Budgets can only be charted with a price,
+/// which also gives numbers of boxes and budgets their cost, and treasure openings and rarities their expected spend.
+pub fn chart(
+    treasure: &Treasure,
+    spec: &ChartSpec,
+    pricing: Option<&Pricing>,
+) -> Result<Chart, Error> {
+    let measure = measure(&spec.rows, &spec.columns)?;
+    let swept = |quantity| spec.rows.quantity() == quantity || spec.columns.quantity() == quantity;
+    let rarity = match swept(Quantity::Rarity) {
+        true => None,
+        false => Some(spec.rarity.as_deref().ok_or_else(|| {
+            Error::InvalidChart("a rarity is needed unless rarity is on an axis".to_owned())
+        })?),
+    };
+    let treasure_opening = (!swept(Quantity::Opening)).then_some(spec.treasure_opening);
+    let fixed = Point {
+        rarity: rarity.unwrap_or_default(),
+        treasure_opening: spec.treasure_opening,
+        boxes: 0,
+    };
+    let row_settings = settings(&spec.rows, pricing)?;
+    let column_settings = settings(&spec.columns, pricing)?;
+
+    let line = |axis: &Axis, i: usize, setting: Setting| {
+        let point = fixed.with(setting);
+        let (boxes, cost) = match setting {
+            Setting::Boxes(boxes) => (
+                matches!(axis, Axis::Budgets(_)).then_some(boxes),
+                pricing.map(|pricing| pricing.cost(boxes)),
+            ),
+            _ => (None, None),
+        };
+        let (expected_value, expected_spend) = match (measure, setting) {
+            (Measure::Probability, Setting::Opening(_) | Setting::Rarity(_)) => {
+                let table = treasure.table(point.rarity)?;
+                (
+                    Some(expected_value(table, point.treasure_opening)?),
+                    pricing
+                        .map(|pricing| pricing.expected_spend(table, point.treasure_opening))
+                        .transpose()?,
+                )
+            }
+            _ => (None, None),
+        };
+        Ok(ChartLine {
+            value: axis.value(i),
+            boxes,
+            cost,
+            expected_value,
+            expected_spend,
+        })
+    };
+    let rows = lines(&spec.rows, &row_settings, line)?;
+    let columns = lines(&spec.columns, &column_settings, line)?;
+
+    // Each rarity and starting treasure's probabilities are only worked out once, as far as the most boxes charted
+    let most_boxes = row_settings
+        .iter()
+        .chain(&column_settings)
+        .filter_map(|setting| match setting {
+            Setting::Boxes(boxes) => Some(*boxes),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut cdfs = HashMap::new();
+    let cells = cells(&row_settings, &column_settings, |row, column| {
+        let point = fixed.with(row).with(column);
+        let table = treasure.table(point.rarity)?;
+        match measure {
+            Measure::ExpectedValue => expected_value(table, point.treasure_opening),
+            Measure::Probability => {
+                let key = (point.rarity, point.treasure_opening);
+                let by = match cdfs.entry(key) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(cdf(table, point.treasure_opening, most_boxes)?)
+                    }
+                };
+                Ok(by[point.boxes])
+            }
+        }
+    })?;
+
+    Ok(Chart {
+        row_quantity: spec.rows.quantity(),
+        column_quantity: spec.columns.quantity(),
+        measure,
+        rarity: rarity.map(str::to_owned),
+        treasure_opening,
+        columns,
+        rows,
+        cells,
+    })
+}
+
+/// Like `chart`, but for getting all of or any of several items, sweeping openings against boxes. Opening `n` is for
+/// having opened `n - 1` more treasures than each target's treasure opening.
+pub fn multi_chart(
+    goal: Goal,
+    targets: &[(&OddsTable, usize)],
+    rows: &Axis,
+    columns: &Axis,
+) -> Result<Chart, Error> {
+    multi::check(targets)?;
+    measure(rows, columns)?;
+    if [rows, columns]
+        .iter()
+        .any(|axis| !matches!(axis, Axis::Openings(_) | Axis::Boxes(_)))
+    {
+        return Err(Error::InvalidChart(
+            "charts of several targets can only have opening and boxes on their axes".to_owned(),
+        ));
+    }
+
+    let shifted = |treasures: usize| -> Vec<_> {
+        targets
+            .iter()
+            .map(|&(table, opening)| (table, opening + treasures - 1))
+            .collect()
+    };
+    let row_settings = settings(rows, None)?;
+    let column_settings = settings(columns, None)?;
+
+    let line = |axis: &Axis, i: usize, setting: Setting| {
+        let expected_value = match setting {
+            Setting::Opening(treasures) => Some(multi::expected_value(goal, &shifted(treasures))?),
+            _ => None,
+        };
+        Ok(ChartLine {
+            value: axis.value(i),
+            boxes: None,
+            cost: None,
+            expected_value,
+            expected_spend: None,
+        })
+    };
+    let fixed = Point {
+        rarity: "",
+        treasure_opening: 1,
+        boxes: 0,
+    };
+    Ok(Chart {
+        row_quantity: rows.quantity(),
+        column_quantity: columns.quantity(),
+        measure: Measure::Probability,
+        rarity: None,
+        treasure_opening: None,
+        rows: lines(rows, &row_settings, line)?,
+        columns: lines(columns, &column_settings, line)?,
+        cells: cells(&row_settings, &column_settings, |row, column| {
+            let point = fixed.with(row).with(column);
+            multi::probability(goal, &shifted(point.treasure_opening), point.boxes)
+        })?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calc::probability,
+        treasure::{Treasures, DEFAULT_TREASURE},
+    };
+
+    fn range(s: &str) -> Range {
+        s.parse().unwrap()
+    }
+
+    fn invalid_range(s: &str) -> String {
+        match s.parse::<Range>() {
+            Err(Error::InvalidRange { reason, .. }) => reason,
+            other => panic!("{s} gave {other:?}"),
+        }
+    }
+
+    fn axis(s: &str) -> Axis {
+        Axis::parse(s, Quantity::Boxes).unwrap()
+    }
+
+    #[test]
+    fn ranges_step_from_the_start() {
+        assert_eq!(
+            range("1..10").values(),
+            (1..=10).map(f64::from).collect::<Vec<_>>()
+        );
+        assert_eq!(range("1..10:3").values(), [1., 4., 7., 10.]);
+        assert_eq!(range("1..9:3").values(), [1., 4., 7.]);
+        assert_eq!(range("0..1:0.25").values(), [0., 0.25, 0.5, 0.75, 1.]);
+        assert_eq!(range(" 7 ").values(), [7.]);
+
+        // Rounding doesn't lose the end
+        let tenths = range("0..1:0.1").values();
+        assert_eq!(tenths.len(), 11);
+        assert!((tenths[10] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_ranges_multiply_by_the_same_amount() {
+        let values = range("1..1000:log4").values();
+        assert_eq!(values.len(), 4);
+        for (value, expected) in values.iter().zip([1., 10., 100., 1000.]) {
+            assert!((value - expected).abs() < 1e-9, "{values:?}");
+        }
+        assert_eq!(range("2..2:log3").values(), [2., 2., 2.]);
+    }
+
+    #[test]
+    fn invalid_ranges_are_rejected() {
+        assert_eq!(invalid_range("5..1"), "the start is past the end");
+        assert_eq!(invalid_range("1..5:0"), "the step must be greater than 0");
+        assert_eq!(invalid_range("1..5:-1"), "the step must be greater than 0");
+        assert_eq!(
+            invalid_range("1..5:log1"),
+            "log-spaced ranges need at least 2 points"
+        );
+        assert_eq!(
+            invalid_range("0..5:log3"),
+            "log-spaced ranges must start above 0"
+        );
+        for syntax in ["", "a..b", "1..", "1..5:", "1..5:logx", "1..inf", "1..NaN"] {
+            assert!(invalid_range(syntax).starts_with("expected"), "{syntax:?}");
+        }
+    }
+
+    #[test]
+    fn ranges_are_limited_in_size() {
+        assert_eq!(range("1..10000").values().len(), MAX_RANGE_VALUES);
+        let too_many = format!("ranges can have at most {MAX_RANGE_VALUES} values");
+        assert_eq!(invalid_range("0..10000"), too_many);
+        assert_eq!(invalid_range("1..1e13"), too_many);
+        assert_eq!(invalid_range("0..1:1e-9"), too_many);
+        assert_eq!(invalid_range("1..2:log10001"), too_many);
+    }
+
+    #[test]
+    fn counts_round_log_values_and_keep_each_once() {
+        assert_eq!(range("1..10:3").counts().unwrap(), [1, 4, 7, 10]);
+        // 1, 2.15, 4.64 and 10
+        assert_eq!(range("1..10:log4").counts().unwrap(), [1, 2, 5, 10]);
+        // Ten values, several of which round to each of 1, 2 and 3
+        assert_eq!(range("1..3:log10").counts().unwrap(), [1, 2, 3]);
+        assert_eq!(range("1..1000:log4").counts().unwrap(), [1, 10, 100, 1000]);
+    }
+
+    #[test]
+    fn counts_are_whole_and_not_too_big() {
+        for fractional in ["0.5..3", "1..2.5", "1..3:0.5"] {
+            assert!(range(fractional).counts().is_err(), "{fractional}");
+        }
+        assert_eq!(
+            range(&format!("1..{MAX_COUNT}:1000"))
+                .counts()
+                .unwrap()
+                .len(),
+            100
+        );
+        assert!(range(&format!("1..{}:1000", MAX_COUNT + 1))
+            .counts()
+            .is_err());
+    }
+
+    #[test]
+    fn axes_parse() {
+        assert_eq!(axis("3"), Axis::Boxes(vec![1, 2, 3]));
+        assert_eq!(
+            Axis::parse("3", Quantity::Opening).unwrap(),
+            Axis::Openings(vec![1, 2, 3])
+        );
+        assert_eq!(axis("opening=2..6:2"), Axis::Openings(vec![2, 4, 6]));
+        assert_eq!(axis("budget=0..5:2.5"), Axis::Budgets(vec![0., 2.5, 5.]));
+        assert_eq!(
+            axis("rarity= rare, ,ultra-rare"),
+            Axis::Rarities(vec!["rare".to_owned(), "ultra-rare".to_owned()])
+        );
+        for invalid in ["opening=0..3", "rarity=", "colour=1..3", "boxes", "-3"] {
+            assert!(Axis::parse(invalid, Quantity::Boxes).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn measures_depend_on_whether_boxes_are_charted() {
+        use Measure::{ExpectedValue, Probability};
+        let axes = [
+            axis("opening=1..2"),
+            axis("boxes=1..2"),
+            axis("budget=1..2"),
+            axis("rarity=rare"),
+        ];
+        // Rows down, columns across, in the order of `axes`
+        let expected = [
+            [
+                None,
+                Some(Probability),
+                Some(Probability),
+                Some(ExpectedValue),
+            ],
+            [Some(Probability), None, None, Some(Probability)],
+            [Some(Probability), None, None, Some(Probability)],
+            [
+                Some(ExpectedValue),
+                Some(Probability),
+                Some(Probability),
+                None,
+            ],
+        ];
+        for (rows, expected) in axes.iter().zip(expected) {
+            for (columns, expected) in axes.iter().zip(expected) {
+                assert_eq!(
+                    measure(rows, columns).ok(),
+                    expected,
+                    "{} against {}",
+                    rows.quantity(),
+                    columns.quantity()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cells_are_the_probability_of_each_opening_and_number_of_boxes() {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure(DEFAULT_TREASURE).unwrap();
+        let table = treasure.table("rare").unwrap();
+        let chart = chart(treasure, &ChartSpec::up_to("rare", 3, 4), None).unwrap();
+        assert_eq!(chart.measure, Measure::Probability);
+        assert_eq!(chart.treasure_opening, None);
+        for (row, opening) in (1..=3).enumerate() {
+            let expected: f64 = expected_value(table, opening).unwrap();
+            assert_eq!(chart.rows[row].expected_value, Some(expected));
+            for (column, boxes) in (1..=4).enumerate() {
+                let expected: f64 = probability(table, opening, boxes).unwrap();
+                let cell = chart.cells[row][column];
+                assert!((cell - expected).abs() < 1e-12, "{opening} {boxes}: {cell}");
+            }
+        }
+    }
+
+    #[test]
+    fn cells_are_the_expected_value_of_each_rarity_and_opening() {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure(DEFAULT_TREASURE).unwrap();
+        let spec = ChartSpec {
+            rows: axis("rarity=rare,ultra-rare"),
+            columns: axis("opening=1..20:log3"),
+            rarity: None,
+            treasure_opening: 1,
+        };
+        let chart = chart(treasure, &spec, None).unwrap();
+        assert_eq!(chart.measure, Measure::ExpectedValue);
+        assert_eq!(chart.rarity, None);
+        for (row, rarity) in ["rare", "ultra-rare"].into_iter().enumerate() {
+            for (column, opening) in [1, 4, 20].into_iter().enumerate() {
+                let table = treasure.table(rarity).unwrap();
+                let expected: f64 = expected_value(table, opening).unwrap();
+                assert_eq!(chart.cells[row][column], expected, "{rarity} {opening}");
+            }
+        }
+    }
+
+    #[test]
+    fn budgets_chart_the_boxes_they_buy() {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure(DEFAULT_TREASURE).unwrap();
+        let table = treasure.table("rare").unwrap();
+        let spec = ChartSpec {
+            rows: axis("opening=2"),
+            columns: axis("budget=0..10:5"),
+            rarity: Some("rare".to_owned()),
+            treasure_opening: 1,
+        };
+        assert!(chart(treasure, &spec, None).is_err());
+
+        let pricing = Pricing::new(2.5, vec![], "$".to_owned()).unwrap();
+        let chart = chart(treasure, &spec, Some(&pricing)).unwrap();
+        for (column, boxes) in [0, 2, 4].into_iter().enumerate() {
+            assert_eq!(chart.columns[column].boxes, Some(boxes));
+            let expected: f64 = probability(table, 2, boxes).unwrap();
+            assert!((chart.cells[0][column] - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn charts_need_a_rarity_of_the_treasure() {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure(DEFAULT_TREASURE).unwrap();
+        let mut spec = ChartSpec::up_to("rare", 2, 2);
+        spec.rarity = None;
+        assert!(chart(treasure, &spec, None).is_err());
+        assert!(chart(treasure, &ChartSpec::up_to("common", 2, 2), None).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fmt, str::FromStr};
+
+use crate::{calc::expected_value, odds::OddsTable, Error};
+
+/// A pack of boxes sold for the price of fewer boxes, such as 11 boxes for the price of 10
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bundle {
+    /// The number of boxes in the pack
+    pub boxes: usize,
+    /// The number of boxes' worth of money the pack costs
+    pub paid: usize,
+}
+
+impl FromStr for Bundle {
+    type Err = Error;
+
+    /// Parse a bundle written as `<boxes>for<paid>`, e.g. `11for10`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidBundle(s.to_owned());
+
+        let (boxes, paid) = s.split_once("for").ok_or_else(invalid)?;
+        let boxes: usize = boxes.trim().parse().map_err(|_| invalid())?;
+        let paid: usize = paid.trim().parse().map_err(|_| invalid())?;
+        if boxes == 0 || paid == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Bundle { boxes, paid })
+    }
+}
+
+/// How much boxes cost, and how to print an amount of money
+#[derive(Clone, Debug)]
+pub struct Pricing {
+    pub price_per_box: f64,
+    pub bundles: Vec<Bundle>,
+    pub currency: String,
+}
+
+impl Pricing {
+    pub fn new(price_per_box: f64, bundles: Vec<Bundle>, currency: String) -> Result<Self, Error> {
+        if !(price_per_box > 0. && price_per_box.is_finite()) {
+            return Err(Error::InvalidPrice(price_per_box));
+        }
+
+        Ok(Pricing {
+            price_per_box,
+            bundles,
+            currency,
+        })
+    }
+
+    /// Every way of buying boxes, including buying a single box
+    fn bundles(&self) -> impl Iterator<Item = Bundle> + '_ {
+        std::iter::once(Bundle { boxes: 1, paid: 1 }).chain(self.bundles.iter().copied())
+    }
+
+    /// The number of boxes' worth of money it costs to buy at least `n` boxes, for every `n` up to `max_boxes`, buying
+    /// them as cheaply as possible
+    fn costs_in_boxes(&self, max_boxes: usize) -> Vec<usize> {
+        let mut costs = vec![0; max_boxes + 1];
+        for n in 1..=max_boxes {
+            costs[n] = self
+                .bundles()
+                .map(|bundle| costs[n.saturating_sub(bundle.boxes)] + bundle.paid)
+                .min()
+                .unwrap();
+        }
+        costs
+    }
+
+    /// The cheapest price of at least `boxes` boxes
+    pub fn cost(&self, boxes: usize) -> f64 {
+        self.costs_in_boxes(boxes)[boxes] as f64 * self.price_per_box
+    }
+
+    /// The most boxes that can be bought without spending more than `budget`
+    pub fn boxes_within(&self, budget: f64) -> Result<usize, Error> {
+        if !(budget >= 0. && budget.is_finite()) {
+            return Err(Error::InvalidBudget(budget));
+        }
+
+        // Leave a little room so that e.g. a budget of 2.5 with a price of 0.25 isn't rounded down to 9 boxes
+        let units = (budget / self.price_per_box + 1e-4).floor().max(0.) as usize;
+
+        // The most boxes that can be bought with each number of boxes' worth of money
+        let mut boxes = vec![0; units + 1];
+        for u in 1..=units {
+            boxes[u] = self
+                .bundles()
+                .filter(|bundle| bundle.paid <= u)
+                .map(|bundle| boxes[u - bundle.paid] + bundle.boxes)
+                .max()
+                .unwrap();
+        }
+        Ok(boxes[units])
+    }
+
+    /// The expected amount of money spent buying boxes, starting with `treasure_opening`, until the item is found
+    pub fn expected_spend(&self, table: &OddsTable, treasure_opening: usize) -> Result<f64, Error> {
+        // Without bundles every box costs the same, so the spend is just the expected number of boxes times the price
+        if self.bundles.is_empty() {
+            return Ok(expected_value::<f64>(table, treasure_opening)? * self.price_per_box);
+        }
+        table.check(treasure_opening)?;
+
+        // The number of boxes opened before reaching the end of the table, after which the odds stay the same
+        let table_boxes = (table.odds.len() + 1).saturating_sub(treasure_opening);
+        let mut costs = Vec::new();
+        let mut spend = 0.;
+        // The probability that we still don't have the item
+        let mut survival = 1.;
+        for (boxes, p) in (1..).zip(table.odds_from(treasure_opening)) {
+            if boxes >= costs.len() {
+                costs = self.costs_in_boxes(2 * boxes);
+            }
+            // Past the end of the table, the rest of the spend is at most what buying the remaining boxes one at a time
+            // would add to what's been spent so far, with a geometric number of boxes to go. Once even that can't
+            // change the sum, stop.
+            let rest = survival * (costs[boxes - 1] as f64 + table.tail_odds());
+            if boxes > table_boxes && rest <= f64::EPSILON * spend {
+                break;
+            }
+            // The chance that this box is the one with the item, times what we've spent by then
+            spend += survival / p * costs[boxes] as f64;
+            survival *= 1. - 1. / p;
+        }
+        Ok(spend * self.price_per_box)
+    }
+
+    /// Print an amount of money in this pricing's currency
+    pub fn display(&self, amount: f64) -> impl fmt::Display + '_ {
+        Money {
+            currency: &self.currency,
+            amount,
+        }
+    }
+}
+
+struct Money<'a> {
+    currency: &'a str,
+    amount: f64,
+}
+
+impl fmt::Display for Money<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:.2}", self.currency, self.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rarity;
+
+    fn pricing(bundles: &[&str]) -> Pricing {
+        let bundles = bundles.iter().map(|b| b.parse().unwrap()).collect();
+        Pricing::new(2.5, bundles, "$".to_owned()).unwrap()
+    }
+
+    #[test]
+    fn bundles_parse() {
+        assert_eq!(
+            "11for10".parse::<Bundle>().unwrap(),
+            Bundle {
+                boxes: 11,
+                paid: 10
+            }
+        );
+        for invalid in ["11", "for10", "0for1", "11for0", "elevenfor10"] {
+            assert!(invalid.parse::<Bundle>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn boxes_are_bought_as_cheaply_as_possible() {
+        let pricing = pricing(&["11for10"]);
+        // 1 to 9 boxes one at a time, 10 and 11 as a bundle, then a bundle and singles
+        let costs = pricing.costs_in_boxes(23);
+        assert_eq!(&costs[..13], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 10, 11]);
+        assert_eq!(costs[22], 20);
+        assert_eq!(costs[23], 21);
+        assert_eq!(pricing.cost(22), 50.);
+    }
+
+    #[test]
+    fn budgets_buy_the_most_boxes() {
+        let pricing = pricing(&["11for10"]);
+        assert_eq!(pricing.boxes_within(0.).unwrap(), 0);
+        assert_eq!(pricing.boxes_within(24.99).unwrap(), 9);
+        assert_eq!(pricing.boxes_within(25.).unwrap(), 11);
+        assert_eq!(pricing.boxes_within(52.5).unwrap(), 23);
+        assert!(pricing.boxes_within(-1.).is_err());
+    }
+
+    #[test]
+    fn spend_without_bundles_is_the_expected_value_times_the_price() {
+        for rarity in Rarity::ALL {
+            let table = rarity.table();
+            let ev: f64 = expected_value(&table, 3).unwrap();
+            assert_eq!(pricing(&[]).expected_spend(&table, 3).unwrap(), ev * 2.5);
+        }
+    }
+
+    #[test]
+    fn a_bundle_that_is_no_discount_changes_nothing() {
+        let table = Rarity::UltraRare.table();
+        let spend = pricing(&["10for10"]).expected_spend(&table, 1).unwrap();
+        let ev: f64 = expected_value(&table, 1).unwrap();
+        assert!((spend - ev * 2.5).abs() < 1e-12 * spend, "{spend}");
+    }
+
+    #[test]
+    fn bundles_lower_the_spend() {
+        let table = Rarity::UltraRare.table();
+        let spend = pricing(&["11for10"]).expected_spend(&table, 1).unwrap();
+        let ev: f64 = expected_value(&table, 1).unwrap();
+        assert!(spend < ev * 2.5);
+        // No bundle saves more than 1 box in 11
+        assert!(spend > ev * 2.5 * 10. / 11.);
+    }
+}
@@ -0,0 +1,278 @@
+use std::error::Error;
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Cell, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+
+use dota_odds_calc::{expected_value, probability, quantile, treasure::Treasure, OddsTable};
+
+/// The width of each opening's column in the odds pane
+const ODDS_COLUMN_WIDTH: u16 = 9;
+
+/// How many boxes PageUp and PageDown add or take away
+const BOXES_PAGE: usize = 10;
+
+/// A full-screen view of one rarity's odds, where the treasure opening and number of boxes are changed with the
+/// arrow keys
+pub struct Dashboard<'a> {
+    treasure: &'a Treasure,
+    tables: Vec<&'a OddsTable>,
+    /// Which of `tables` is shown
+    selected: usize,
+    treasure_opening: usize,
+    num_boxes: usize,
+    // Calculated from the above whenever they change
+    expected_value: f64,
+    probability: f64,
+    /// The probability of getting the item within each number of boxes, far enough to be 99% sure of getting it
+    cdf: Vec<(f64, f64)>,
+}
+
+impl<'a> Dashboard<'a> {
+    pub fn new(
+        treasure: &'a Treasure,
+        rarity: &str,
+        treasure_opening: usize,
+        num_boxes: usize,
+    ) -> Result<Self, dota_odds_calc::Error> {
+        treasure.table(rarity)?;
+        let tables: Vec<_> = treasure.tables.iter().collect();
+        let mut dashboard = Dashboard {
+            treasure,
+            selected: tables
+                .iter()
+                .position(|table| table.name == rarity)
+                .unwrap(),
+            tables,
+            treasure_opening,
+            num_boxes: num_boxes.max(1),
+            expected_value: 0.,
+            probability: 0.,
+            cdf: Vec::new(),
+        };
+        dashboard.calculate()?;
+        Ok(dashboard)
+    }
+
+    fn table(&self) -> &'a OddsTable {
+        self.tables[self.selected]
+    }
+
+    fn calculate(&mut self) -> Result<(), dota_odds_calc::Error> {
+        let table = self.table();
+        self.expected_value = expected_value(table, self.treasure_opening)?;
+        self.probability = probability(table, self.treasure_opening, self.num_boxes)?;
+
+        let max_boxes = quantile(table, self.treasure_opening, 0.99)?
+            .unwrap_or(self.num_boxes)
+            .max(self.num_boxes);
+        self.cdf = (0..=max_boxes)
+            .map(|n| Ok((n as f64, probability(table, self.treasure_opening, n)?)))
+            .collect::<Result<_, dota_odds_calc::Error>>()?;
+        Ok(())
+    }
+
+    /// Respond to a key press. Returns false when it's time to leave.
+    pub fn handle_key(&mut self, key: KeyCode) -> Result<bool, dota_odds_calc::Error> {
+        match key {
+            KeyCode::Left => self.treasure_opening = (self.treasure_opening - 1).max(1),
+            KeyCode::Right => self.treasure_opening += 1,
+            KeyCode::Up => self.num_boxes += 1,
+            KeyCode::Down => self.num_boxes = (self.num_boxes - 1).max(1),
+            KeyCode::PageUp => self.num_boxes += BOXES_PAGE,
+            KeyCode::PageDown => self.num_boxes = self.num_boxes.saturating_sub(BOXES_PAGE).max(1),
+            KeyCode::Tab => self.selected = (self.selected + 1) % self.tables.len(),
+            KeyCode::BackTab => {
+                self.selected = (self.selected + self.tables.len() - 1) % self.tables.len()
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            _ => return Ok(true),
+        }
+        self.calculate()?;
+        Ok(true)
+    }
+
+    /// Draw every pane of the dashboard into the frame
+    pub fn draw(&self, frame: &mut Frame) {
+        let [header, numbers, cdf, odds, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(4),
+            Constraint::Min(8),
+            Constraint::Length(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [ev, prob] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(numbers);
+
+        frame.render_widget(
+            Line::from(format!(
+                "{} ({}), treasure opening {}, {} boxes",
+                self.table().name,
+                self.treasure.name,
+                self.treasure_opening,
+                self.num_boxes
+            ))
+            .bold(),
+            header,
+        );
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!("{:.2} boxes", self.expected_value)).bold(),
+                Line::from(format!("from treasure opening {}", self.treasure_opening)),
+            ])
+            .block(Block::bordered().title("Expected value")),
+            ev,
+        );
+
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title("Probability"))
+                .gauge_style(Style::new().fg(Color::Green))
+                .ratio(self.probability.clamp(0., 1.))
+                .label(format!(
+                    "{:.2}% within {} boxes",
+                    self.probability * 100.,
+                    self.num_boxes
+                )),
+            prob,
+        );
+
+        self.draw_cdf(frame, cdf);
+        self.draw_odds(frame, odds);
+
+        frame.render_widget(
+            Line::from(
+                "←/→ treasure opening   ↑/↓ boxes   PgUp/PgDn ±10 boxes   Tab rarity   q quit",
+            )
+            .dim(),
+            help,
+        );
+    }
+
+    fn draw_cdf(&self, frame: &mut Frame, area: Rect) {
+        let max_boxes = self.cdf.last().map_or(1., |&(n, _)| n.max(1.));
+        let selected = [(self.num_boxes as f64, self.probability)];
+        let datasets = vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Cyan))
+                .data(&self.cdf),
+            Dataset::default()
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().fg(Color::Yellow))
+                .data(&selected),
+        ];
+
+        frame.render_widget(
+            Chart::new(datasets)
+                .block(Block::bordered().title("Probability by number of boxes"))
+                .x_axis(
+                    Axis::default().bounds([0., max_boxes]).labels(
+                        [0, max_boxes as usize / 2, max_boxes as usize].map(|n| n.to_string()),
+                    ),
+                )
+                .y_axis(
+                    Axis::default()
+                        .bounds([0., 1.])
+                        .labels(["0%", "50%", "100%"]),
+                ),
+            area,
+        );
+    }
+
+    /// The odds of the openings around the current one, with the current one highlighted
+    fn draw_odds(&self, frame: &mut Frame, area: Rect) {
+        let columns = (area.width.saturating_sub(2) / ODDS_COLUMN_WIDTH).max(2) as usize - 1;
+        // Keep a few earlier openings in view, so the odds can be seen escalating
+        let first = self.treasure_opening.saturating_sub(columns / 4).max(1);
+        let highlight = |opening: usize| {
+            if opening == self.treasure_opening {
+                Style::new().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::new()
+            }
+        };
+
+        let openings = (first..first + columns)
+            .map(|opening| Cell::from(opening.to_string()).style(highlight(opening)));
+        let odds = self
+            .table()
+            .odds_from(first)
+            .zip(first..first + columns)
+            .map(|(odds, opening)| Cell::from(format!("{odds}")).style(highlight(opening)));
+
+        frame.render_widget(
+            Table::new(
+                [
+                    Row::new(std::iter::once(Cell::from("opening")).chain(openings)),
+                    Row::new(std::iter::once(Cell::from("1 in")).chain(odds)),
+                ],
+                vec![Constraint::Length(ODDS_COLUMN_WIDTH); columns + 1],
+            )
+            .block(Block::bordered().title(format!("{} odds", self.table().name))),
+            area,
+        );
+    }
+
+    /// Show the dashboard until the user leaves
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use dota_odds_calc::treasure::Treasures;
+
+    use super::*;
+
+    fn render(dashboard: &Dashboard) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn arrow_keys_update_the_panes() {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure("standard").unwrap();
+        let mut dashboard = Dashboard::new(treasure, "rare", 1, 10).unwrap();
+        let screen = render(&dashboard);
+        assert!(screen.contains("12.93 boxes"));
+        assert!(screen.contains("within 10 boxes"));
+        assert!(screen.contains("rare odds"));
+
+        dashboard.handle_key(KeyCode::Right).unwrap();
+        dashboard.handle_key(KeyCode::Right).unwrap();
+        dashboard.handle_key(KeyCode::Up).unwrap();
+        let screen = render(&dashboard);
+        assert!(screen.contains("10.95 boxes"));
+        assert!(screen.contains("within 11 boxes"));
+    }
+}
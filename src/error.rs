@@ -0,0 +1,140 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong while loading odds or calculating with them
+#[derive(Debug)]
+pub enum Error {
+    /// Treasure openings start at 1
+    InvalidTreasureOpening(usize),
+    /// An odds table that can't be calculated with, such as one with no openings or with odds better than 1 in 1
+    InvalidOddsTable { table: String, reason: String },
+    /// A confidence level outside of [0, 1]
+    InvalidConfidenceLevel(f64),
+    /// A price per box that isn't a positive number
+    InvalidPrice(f64),
+    /// A budget that isn't a non-negative number
+    InvalidBudget(f64),
+    /// A bundle that couldn't be parsed
+    InvalidBundle(String),
+    /// A multi-item target that couldn't be parsed
+    InvalidTarget(String),
+    /// A multi-item calculation without any items
+    NoTargets,
+    /// Simulations need at least 2 trials to say how reliable they are
+    NotEnoughTrials(usize),
+    UnknownTreasure {
+        treasure: String,
+        known: Vec<String>,
+    },
+    UnknownRarity {
+        treasure: String,
+        rarity: String,
+        known: Vec<String>,
+    },
+    /// An odds file that couldn't be read
+    Io(io::Error),
+    /// An odds file that couldn't be parsed
+    ParseOddsFile(String),
+    /// An odds file that couldn't be serialized or written
+    WriteOddsFile(String),
+    /// A plot that couldn't be rendered
+    Render(String),
+    /// A ledger of opened boxes that couldn't be read, parsed or saved
+    Ledger(String),
+    /// An openings log that couldn't be read or parsed
+    ParseLog(String),
+    /// An overlap policy for the joint model that isn't known
+    InvalidOverlap(String),
+    /// The same rarity given as more than one tier of the joint model
+    DuplicateTier(String),
+    /// Tiers with more combinations of treasure openings and drops than the joint model can track (the limit is given)
+    TooManyStates(usize),
+    /// A run of boxes too long to work out the number of items got in (the limit is given)
+    TooManyBoxes(usize),
+    /// A server that couldn't start listening
+    Serve(String),
+    /// A chart axis that couldn't be parsed
+    InvalidAxis(String),
+    /// A range of chart values that couldn't be parsed or can't be swept
+    InvalidRange { range: String, reason: String },
+    /// A combination of chart axes that can't be charted
+    InvalidChart(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidTreasureOpening(opening) => {
+                write!(f, "Treasure opening must be 1 or greater, got {opening}")
+            }
+            Error::InvalidOddsTable { table, reason } => {
+                write!(f, "Invalid odds table `{table}`: {reason}")
+            }
+            Error::InvalidConfidenceLevel(level) => {
+                write!(f, "Confidence level {level} must be between 0 and 1")
+            }
+            Error::InvalidPrice(price) => {
+                write!(f, "Price per box must be greater than 0, got {price}")
+            }
+            Error::InvalidBudget(budget) => write!(f, "Budget must be 0 or greater, got {budget}"),
+            Error::InvalidBundle(bundle) => write!(
+                f,
+                "Invalid bundle `{bundle}`, expected something like `11for10`"
+            ),
+            Error::InvalidTarget(target) => write!(
+                f,
+                "Invalid target `{target}`, expected <rarity>:<treasure opening> with an opening of 1 or greater"
+            ),
+            Error::NoTargets => write!(f, "At least one target is required"),
+            Error::NotEnoughTrials(trials) => {
+                write!(f, "Simulating needs at least 2 trials, got {trials}")
+            }
+            Error::UnknownTreasure { treasure, known } => write!(
+                f,
+                "Unknown treasure `{treasure}`, expected one of: {}",
+                known.join(", ")
+            ),
+            Error::UnknownRarity {
+                treasure,
+                rarity,
+                known,
+            } => write!(
+                f,
+                "Unknown rarity `{rarity}` for treasure `{treasure}`, expected one of: {}",
+                known.join(", ")
+            ),
+            Error::Io(e) => write!(f, "Could not read odds file: {e}"),
+            Error::ParseOddsFile(e) => write!(f, "Could not parse odds file: {e}"),
+            Error::WriteOddsFile(e) => write!(f, "Could not write odds file: {e}"),
+            Error::Render(e) => write!(f, "Could not render plot: {e}"),
+            Error::Ledger(e) => write!(f, "Could not use ledger: {e}"),
+            Error::ParseLog(e) => write!(f, "Could not read openings log: {e}"),
+            Error::InvalidOverlap(overlap) => write!(
+                f,
+                "Invalid overlap `{overlap}`, expected rarest-first or normalize"
+            ),
+            Error::DuplicateTier(rarity) => write!(f, "Rarity `{rarity}` was given more than once"),
+            Error::TooManyStates(max) => write!(
+                f,
+                "The tiers have more than {max} combinations of treasure openings and drops to track, try fewer tiers or shorter odds tables"
+            ),
+            Error::TooManyBoxes(max) => {
+                write!(f, "Drops can only be worked out for up to {max} boxes")
+            }
+            Error::Serve(e) => write!(f, "Could not start server: {e}"),
+            Error::InvalidAxis(axis) => write!(
+                f,
+                "Invalid chart axis `{axis}`, expected <quantity>=<values> with a quantity of opening, boxes, budget or rarity"
+            ),
+            Error::InvalidRange { range, reason } => write!(f, "Invalid range `{range}`: {reason}"),
+            Error::InvalidChart(reason) => write!(f, "Invalid chart: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,274 @@
+use std::{ops::Range, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    odds::{OddsTable, Tail},
+    stats::beta_quantile,
+    Error,
+};
+
+/// The Beta prior on each opening's drop probability: Jeffreys' prior, which lets the logged openings speak for
+/// themselves without claiming any drop probability is impossible
+pub const PRIOR: (f64, f64) = (0.5, 0.5);
+
+/// One logged box: the treasure opening it was on and the rarity it dropped. Anything that isn't the rarity being
+/// looked at (such as an empty result or `none`) counts as not dropping it.
+#[derive(Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub opening: usize,
+    #[serde(default)]
+    pub result: String,
+}
+
+/// Read an openings log: a CSV file with an `opening,result` header and a row for each box
+pub fn read_log(path: &Path) -> Result<Vec<LogEntry>, Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| Error::ParseLog(e.to_string()))?;
+    let log = rdr
+        .deserialize()
+        .collect::<Result<Vec<LogEntry>, _>>()
+        .map_err(|e| Error::ParseLog(e.to_string()))?;
+
+    if let Some(entry) = log.iter().find(|entry| entry.opening == 0) {
+        return Err(Error::InvalidTreasureOpening(entry.opening));
+    }
+    Ok(log)
+}
+
+/// How many logged boxes were on one opening, and how many of them dropped the rarity
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counts {
+    pub boxes: u64,
+    pub drops: u64,
+}
+
+/// The counts of every opening from 1 to the highest one logged
+pub fn count(log: &[LogEntry], rarity: &str) -> Vec<Counts> {
+    let mut counts = vec![Counts::default(); log.iter().map(|e| e.opening).max().unwrap_or(0)];
+    for entry in log {
+        let counts = &mut counts[entry.opening - 1];
+        counts.boxes += 1;
+        if entry.result == rarity {
+            counts.drops += 1;
+        }
+    }
+    counts
+}
+
+/// The estimated drop probability of one opening
+#[derive(Serialize, Clone, Debug)]
+pub struct Fit {
+    pub treasure_opening: usize,
+    pub boxes: u64,
+    pub drops: u64,
+    /// The mean of the posterior, which is what the fitted odds table uses
+    pub probability: f64,
+    /// The ends of the equal-tailed credible interval
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The mean of the posterior drop probability of an opening (or a pool of them) with these counts
+fn posterior_mean(c: &Counts) -> f64 {
+    (PRIOR.0 + c.drops as f64) / (PRIOR.0 + PRIOR.1 + c.boxes as f64)
+}
+
+/// Pool neighbouring openings until the estimated drop probability never goes down from one opening to the next (the
+/// pool adjacent violators algorithm). The estimate compared is the posterior mean that `fit` reports, so sparse
+/// openings whose estimates lean on the prior get pooled too. Returns the range of openings in each pool with their
+/// combined counts.
+fn pool_adjacent_violators(counts: &[Counts]) -> Vec<(Range<usize>, Counts)> {
+    let mut pools: Vec<(Range<usize>, Counts)> = Vec::new();
+    for (i, &c) in counts.iter().enumerate() {
+        // Openings without any boxes say nothing about the order, so they join whichever pool they're next to
+        if c.boxes == 0 {
+            match pools.last_mut() {
+                Some(last) => last.0.end = i + 1,
+                None => pools.push((i..i + 1, c)),
+            }
+            continue;
+        }
+
+        pools.push((i..i + 1, c));
+        while pools.len() > 1 {
+            let (range, c) = pools.pop().unwrap();
+            let (previous, p) = pools.last_mut().unwrap();
+            if p.boxes > 0 && posterior_mean(p) <= posterior_mean(&c) {
+                pools.push((range, c));
+                break;
+            }
+            previous.end = range.end;
+            p.boxes += c.boxes;
+            p.drops += c.drops;
+        }
+    }
+    pools
+}
+
+/// Estimate the drop probability of every opening from its counts, with a credible interval holding `level` of the
+/// posterior. With `monotone`, the estimates are constrained to never go down from one opening to the next, by pooling
+/// the openings that would.
+pub fn fit(counts: &[Counts], monotone: bool, level: f64) -> Result<Vec<Fit>, Error> {
+    if !(0.0..=1.0).contains(&level) {
+        return Err(Error::InvalidConfidenceLevel(level));
+    }
+
+    let pools = if monotone {
+        pool_adjacent_violators(counts)
+    } else {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (i..i + 1, c))
+            .collect()
+    };
+
+    let tail = (1. - level) / 2.;
+    Ok(pools
+        .into_iter()
+        .flat_map(|(range, pooled)| {
+            let a = PRIOR.0 + pooled.drops as f64;
+            let b = PRIOR.1 + (pooled.boxes - pooled.drops) as f64;
+            let (lower, upper) = (beta_quantile(tail, a, b), beta_quantile(1. - tail, a, b));
+            range.map(move |i| Fit {
+                treasure_opening: i + 1,
+                boxes: counts[i].boxes,
+                drops: counts[i].drops,
+                probability: posterior_mean(&pooled),
+                lower,
+                upper,
+            })
+        })
+        .collect())
+}
+
+/// An odds table of the fitted drop probabilities, which keeps using the last one for openings past the log
+pub fn fitted_table(fits: &[Fit], name: &str) -> OddsTable {
+    OddsTable {
+        name: name.to_owned(),
+        odds: fits.iter().map(|fit| 1. / fit.probability).collect(),
+        tail: Tail::RepeatLast,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(counts: &[(u64, u64)]) -> Vec<Counts> {
+        counts
+            .iter()
+            .map(|&(boxes, drops)| Counts { boxes, drops })
+            .collect()
+    }
+
+    #[test]
+    fn drop_rates_that_go_down_are_pooled() {
+        let pools = pool_adjacent_violators(&counts(&[(10, 5), (10, 1), (10, 8)]));
+        assert_eq!(
+            pools,
+            [
+                (
+                    0..2,
+                    Counts {
+                        boxes: 20,
+                        drops: 6
+                    }
+                ),
+                (
+                    2..3,
+                    Counts {
+                        boxes: 10,
+                        drops: 8
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn pooling_repeats_until_nothing_goes_down() {
+        // Pooling the last two makes a rate of 0.3, which is then below the first
+        let pools = pool_adjacent_violators(&counts(&[(10, 4), (10, 5), (10, 1)]));
+        assert_eq!(
+            pools,
+            [(
+                0..3,
+                Counts {
+                    boxes: 30,
+                    drops: 10
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn openings_without_boxes_join_their_neighbours() {
+        let pools = pool_adjacent_violators(&counts(&[(10, 1), (0, 0), (10, 5)]));
+        assert_eq!(
+            pools,
+            [
+                (
+                    0..2,
+                    Counts {
+                        boxes: 10,
+                        drops: 1
+                    }
+                ),
+                (
+                    2..3,
+                    Counts {
+                        boxes: 10,
+                        drops: 5
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn fits_are_posterior_means() {
+        let counts = counts(&[(10, 5), (10, 1), (10, 8)]);
+        let fits = fit(&counts, true, 0.9).unwrap();
+        // Both pooled openings share Beta(0.5 + 6, 0.5 + 14), but keep their own counts
+        assert_eq!(fits[0].probability, 6.5 / 21.);
+        assert_eq!(fits[1].probability, 6.5 / 21.);
+        assert_eq!((fits[1].boxes, fits[1].drops), (10, 1));
+        assert_eq!(fits[0].lower, fits[1].lower);
+        assert!(fits[0].lower < fits[0].probability && fits[0].probability < fits[0].upper);
+
+        let unpooled = fit(&counts, false, 0.9).unwrap();
+        assert_eq!(unpooled[1].probability, 1.5 / 11.);
+        assert!(fit(&counts, false, 1.5).is_err());
+    }
+
+    #[test]
+    fn monotone_fits_never_go_down() {
+        // A lone box without a drop has a posterior mean of 1/4, far above the second opening's 1.5/51
+        let sparse = counts(&[(1, 0), (50, 1)]);
+        let fits = fit(&sparse, true, 0.9).unwrap();
+        assert_eq!(fits[0].probability, 1.5 / 52.);
+        assert_eq!(fits[1].probability, 1.5 / 52.);
+
+        let counts = counts(&[
+            (0, 0),
+            (3, 0),
+            (1, 1),
+            (40, 2),
+            (2, 0),
+            (0, 0),
+            (25, 9),
+            (1, 0),
+        ]);
+        let fits = fit(&counts, true, 0.9).unwrap();
+        assert_eq!(fits.len(), counts.len());
+        assert!(fits
+            .windows(2)
+            .all(|pair| pair[0].probability <= pair[1].probability));
+        let table = fitted_table(&fits, "fitted");
+        assert!(table.odds.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+}
@@ -0,0 +1,208 @@
+//! A C ABI for embedding the calculator in programs written in other languages. Every function returns a `DocStatus`
+//! instead of panicking, writes its result through an out pointer, and leaves a message for `doc_last_error` when it
+//! fails. The odds tables are shared by every thread, and start out as the built-in ones.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr,
+    sync::{LazyLock, Mutex},
+};
+
+use crate::{
+    calc::{expected_value, probability},
+    odds::OddsFile,
+    treasure::{Treasures, DEFAULT_TREASURE},
+    Error,
+};
+
+/// What a function returned. Anything but `Ok` comes with a message from `doc_last_error`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocStatus {
+    Ok = 0,
+    /// A pointer argument was null
+    NullPointer = 1,
+    /// A string argument wasn't valid UTF-8
+    InvalidUtf8 = 2,
+    /// Treasure openings start at 1
+    InvalidTreasureOpening = 3,
+    UnknownRarity = 4,
+    UnknownTreasure = 5,
+    /// An odds file that couldn't be read
+    Io = 6,
+    /// An odds file that couldn't be parsed, or that has a table that can't be calculated with
+    InvalidOdds = 7,
+    /// A bug in the calculator. Please report it!
+    Panic = 8,
+}
+
+/// The odds tables the functions calculate with, and the treasure they're looked up in
+struct State {
+    treasures: Treasures,
+    treasure: String,
+}
+
+static STATE: LazyLock<Mutex<State>> = LazyLock::new(|| {
+    Mutex::new(State {
+        treasures: Treasures::builtin(),
+        treasure: DEFAULT_TREASURE.to_owned(),
+    })
+});
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failed call, with the status to return and the message to leave for `doc_last_error`
+struct Failure(DocStatus, String);
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        let status = match e {
+            Error::InvalidTreasureOpening(_) => DocStatus::InvalidTreasureOpening,
+            Error::UnknownRarity { .. } => DocStatus::UnknownRarity,
+            Error::UnknownTreasure { .. } => DocStatus::UnknownTreasure,
+            Error::Io(_) => DocStatus::Io,
+            _ => DocStatus::InvalidOdds,
+        };
+        Failure(status, e.to_string())
+    }
+}
+
+/// Run `f`, turning its errors and panics into a status and a message for `doc_last_error`
+fn call(f: impl FnOnce() -> Result<(), Failure>) -> DocStatus {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(Failure(DocStatus::Panic, format!("Panicked: {message}")))
+    });
+    let (status, error) = match result {
+        Ok(()) => (DocStatus::Ok, None),
+        // Messages never have a nul in them, but if one did, a message cut short is better than none
+        Err(Failure(status, message)) => (
+            status,
+            Some(CString::new(message.replace('\0', "")).unwrap_or_default()),
+        ),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+    status
+}
+
+/// Borrow a C string argument
+///
+/// # Safety
+/// `s` must be null or point to a nul-terminated string that outlives the returned `&str`
+unsafe fn string<'a>(s: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(Failure(DocStatus::NullPointer, format!("`{name}` is null")));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| {
+        Failure(
+            DocStatus::InvalidUtf8,
+            format!("`{name}` isn't valid UTF-8"),
+        )
+    })
+}
+
+/// Write a result through an out pointer
+///
+/// # Safety
+/// `out` must be null or valid for writes
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), Failure> {
+    if out.is_null() {
+        return Err(Failure(DocStatus::NullPointer, "`out` is null".to_owned()));
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// Calculate the expected number of boxes, starting with `treasure_opening`, needed to get an item of `rarity`
+///
+/// # Safety
+/// `rarity` must be a nul-terminated string, and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn doc_expected_value(
+    rarity: *const c_char,
+    treasure_opening: usize,
+    out: *mut f64,
+) -> DocStatus {
+    call(|| {
+        let rarity = string(rarity, "rarity")?;
+        let state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        let table = state.treasures.treasure(&state.treasure)?.table(rarity)?;
+        write(out, expected_value(table, treasure_opening)?)
+    })
+}
+
+/// Calculate the probability of getting an item of `rarity` within `boxes` boxes, starting with `treasure_opening`
+///
+/// # Safety
+/// `rarity` must be a nul-terminated string, and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn doc_probability(
+    rarity: *const c_char,
+    treasure_opening: usize,
+    boxes: usize,
+    out: *mut f64,
+) -> DocStatus {
+    call(|| {
+        let rarity = string(rarity, "rarity")?;
+        let state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        let table = state.treasures.treasure(&state.treasure)?.table(rarity)?;
+        write(out, probability(table, treasure_opening, boxes)?)
+    })
+}
+
+/// Load the odds tables of an odds file (TOML, or JSON if the path ends in .json). Tables are added to the treasure
+/// the file names, or to the built-in one, replacing any tables with the same names.
+///
+/// # Safety
+/// `path` must be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn doc_load_odds_file(path: *const c_char) -> DocStatus {
+    call(|| {
+        let file = OddsFile::load(Path::new(string(path, "path")?))?;
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        state.treasures.add_file(file);
+        Ok(())
+    })
+}
+
+/// Calculate with the odds tables of `treasure` from now on. It's `standard`, the built-in treasure, to start with.
+///
+/// # Safety
+/// `treasure` must be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn doc_set_treasure(treasure: *const c_char) -> DocStatus {
+    call(|| {
+        let treasure = string(treasure, "treasure")?;
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        state.treasures.treasure(treasure)?;
+        state.treasure = treasure.to_owned();
+        Ok(())
+    })
+}
+
+/// Go back to the built-in odds tables, forgetting any loaded odds files
+#[no_mangle]
+pub extern "C" fn doc_reset_odds() -> DocStatus {
+    call(|| {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        state.treasures = Treasures::builtin();
+        state.treasure = DEFAULT_TREASURE.to_owned();
+        Ok(())
+    })
+}
+
+/// The message of the last call on this thread that failed, or null if it succeeded. The string belongs to the
+/// library and is only valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn doc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
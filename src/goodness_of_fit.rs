@@ -0,0 +1,165 @@
+use serde::Serialize;
+
+use crate::{estimate::Counts, odds::OddsTable, stats::chi_square_survival, Error};
+
+/// How one opening of a log compares with the published odds
+#[derive(Serialize, Clone, Debug)]
+pub struct OpeningDeviation {
+    pub treasure_opening: usize,
+    pub boxes: u64,
+    pub drops: u64,
+    /// The drop probability of the published odds
+    pub published: f64,
+    /// The fraction of the opening's boxes that dropped the rarity
+    pub observed: f64,
+    /// The number of drops the published odds expect
+    pub expected_drops: f64,
+    /// How many standard deviations the drops are from what's expected. Positive when the rarity dropped more often
+    /// than the published odds say it should.
+    pub z: f64,
+    /// This opening's part of the likelihood ratio statistic. The openings with the largest share deviate the most.
+    pub g: f64,
+}
+
+/// The result of testing a log of openings against an odds table
+#[derive(Serialize, Clone, Debug)]
+pub struct GoodnessOfFit {
+    /// The likelihood ratio (G) statistic of the whole log
+    pub g: f64,
+    /// The probability of a G statistic at least this large if the published odds are right
+    pub g_p_value: f64,
+    /// Pearson's chi-square statistic, which approximates G but is less reliable when few drops are expected
+    pub chi_square: f64,
+    pub chi_square_p_value: f64,
+    /// One for each opening with at least one logged box
+    pub degrees_of_freedom: usize,
+    /// Every opening with at least one logged box, the ones that deviate most from the published odds first
+    pub openings: Vec<OpeningDeviation>,
+}
+
+/// `observed * ln(observed / expected)`, which is 0 when nothing was observed
+fn log_ratio(observed: f64, expected: f64) -> f64 {
+    if observed == 0. {
+        0.
+    } else {
+        observed * (observed / expected).ln()
+    }
+}
+
+/// Test whether the drops logged at each opening are consistent with `table`, with a likelihood ratio (G) test and
+/// Pearson's chi-square test. Each opening's drops are compared with the binomial distribution its odds imply.
+pub fn test(counts: &[Counts], table: &OddsTable) -> Result<GoodnessOfFit, Error> {
+    table.validate()?;
+
+    let mut openings: Vec<_> = counts
+        .iter()
+        .zip(table.odds_from(1))
+        .enumerate()
+        .filter(|(_, (counts, _))| counts.boxes > 0)
+        .map(|(i, (counts, odds))| {
+            let p = 1. / odds;
+            let n = counts.boxes as f64;
+            let drops = counts.drops as f64;
+            let expected_drops = n * p;
+            let variance = expected_drops * (1. - p);
+            OpeningDeviation {
+                treasure_opening: i + 1,
+                boxes: counts.boxes,
+                drops: counts.drops,
+                published: p,
+                observed: drops / n,
+                expected_drops,
+                // Odds of 1 in 1 leave no room for chance, so any deviation from them is infinitely unlikely
+                z: if variance == 0. && drops == expected_drops {
+                    0.
+                } else {
+                    (drops - expected_drops) / variance.sqrt()
+                },
+                g: 2.
+                    * (log_ratio(drops, expected_drops) + log_ratio(n - drops, n - expected_drops)),
+            }
+        })
+        .collect();
+
+    let g = openings.iter().map(|opening| opening.g).sum();
+    let chi_square = openings.iter().map(|opening| opening.z * opening.z).sum();
+    let degrees_of_freedom = openings.len();
+
+    openings.sort_by(|a, b| b.g.total_cmp(&a.g));
+
+    Ok(GoodnessOfFit {
+        g,
+        g_p_value: chi_square_survival(g, degrees_of_freedom),
+        chi_square,
+        chi_square_p_value: chi_square_survival(chi_square, degrees_of_freedom),
+        degrees_of_freedom,
+        openings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tail;
+
+    /// A coin flip at every opening
+    fn coin() -> OddsTable {
+        OddsTable {
+            name: "coin".to_owned(),
+            odds: vec![2.],
+            tail: Tail::RepeatLast,
+        }
+    }
+
+    #[test]
+    fn drops_as_expected_fit_perfectly() {
+        let result = test(
+            &[Counts {
+                boxes: 10,
+                drops: 5,
+            }],
+            &coin(),
+        )
+        .unwrap();
+        assert_eq!((result.g, result.chi_square), (0., 0.));
+        assert_eq!(result.g_p_value, 1.);
+        assert_eq!(result.degrees_of_freedom, 1);
+    }
+
+    #[test]
+    fn statistics_match_hand_calculations() {
+        let result = test(
+            &[Counts {
+                boxes: 100,
+                drops: 60,
+            }],
+            &coin(),
+        )
+        .unwrap();
+        // z = (60 - 50) / sqrt(100 * 0.5 * 0.5) = 2
+        assert!((result.openings[0].z - 2.).abs() < 1e-12);
+        assert!((result.chi_square - 4.).abs() < 1e-12);
+        // G = 2 * (60 ln(60 / 50) + 40 ln(40 / 50))
+        assert!((result.g - 4.027_102_710_137_775).abs() < 1e-12);
+        assert!((result.chi_square_p_value - 0.045_500_263_896_358_4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn openings_without_boxes_are_left_out_and_the_worst_comes_first() {
+        let counts = [
+            Counts {
+                boxes: 10,
+                drops: 5,
+            },
+            Counts::default(),
+            Counts {
+                boxes: 10,
+                drops: 9,
+            },
+        ];
+        let result = test(&counts, &coin()).unwrap();
+        assert_eq!(result.degrees_of_freedom, 2);
+        let openings: Vec<_> = result.openings.iter().map(|o| o.treasure_opening).collect();
+        assert_eq!(openings, [3, 1]);
+    }
+}
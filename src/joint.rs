@@ -0,0 +1,341 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::{calc::expected_value, multi, odds::OddsTable, Error};
+
+/// The most states (combinations of every tier's treasure opening, or of it not having dropped yet) the joint model
+/// will track
+pub const MAX_STATES: usize = 1 << 20;
+
+/// What to do when the tiers' chances for a box add up to more than 1. A box can only drop one item, so the chances
+/// can't all be honored at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlap {
+    /// The rarest tier keeps its full chance, then the next rarest takes what it can of what's left, and so on. Common
+    /// items get whatever chance the tiers leave over.
+    RarestFirst,
+    /// Every tier's chance is scaled down by the same amount so they add up to 1
+    Normalize,
+}
+
+impl FromStr for Overlap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rarest-first" => Ok(Overlap::RarestFirst),
+            "normalize" => Ok(Overlap::Normalize),
+            _ => Err(Error::InvalidOverlap(s.to_owned())),
+        }
+    }
+}
+
+/// What to expect from a run of boxes when every box drops exactly one item: one of the tiers, or a common item
+#[derive(Serialize, Clone, Debug)]
+pub struct JointResult {
+    pub boxes: usize,
+    /// The probability of getting at least one item of every tier
+    pub all: f64,
+    /// The probability of getting at least one item of any tier
+    pub any: f64,
+    /// The expected number of items of each tier, in the order the tiers were given
+    pub expected_drops: Vec<f64>,
+    /// The expected number of boxes that drop a common item instead of any of the tiers
+    pub expected_common: f64,
+}
+
+/// The chance of each outcome of a box, given the chance each tier would have on its own, in order from rarest to
+/// most common. The last outcome is a common item.
+fn outcome_probabilities(chances: &[f64], overlap: Overlap) -> Vec<f64> {
+    let total: f64 = chances.iter().sum();
+    let mut outcomes: Vec<f64> = match overlap {
+        Overlap::RarestFirst => chances
+            .iter()
+            .scan(1., |left, &chance| {
+                let outcome = f64::min(chance, *left);
+                *left -= outcome;
+                Some(outcome)
+            })
+            .collect(),
+        Overlap::Normalize if total > 1. => chances.iter().map(|chance| chance / total).collect(),
+        Overlap::Normalize => chances.to_vec(),
+    };
+    let common = (1. - outcomes.iter().sum::<f64>()).max(0.);
+    outcomes.push(common);
+    outcomes
+}
+
+/// Open `num_boxes` boxes, where every box drops exactly one item: an item of one of the tiers, or a common item. Each
+/// tier keeps its own treasure opening, which goes back to 1 when that tier drops and goes up by 1 otherwise. Tiers
+/// are given as odds tables with the treasure opening each one is on, like the targets of `multi`. When the tiers'
+/// chances for a box add up to more than 1, `overlap` decides how they share it.
+pub fn joint(
+    tiers: &[(&OddsTable, usize)],
+    overlap: Overlap,
+    num_boxes: usize,
+) -> Result<JointResult, Error> {
+    multi::check(tiers)?;
+    for (i, (table, _)) in tiers.iter().enumerate() {
+        if tiers[..i].iter().any(|(other, _)| other.name == table.name) {
+            return Err(Error::DuplicateTier(table.name.clone()));
+        }
+    }
+
+    // Every opening past the end of a table has the same odds, so they can all share that tier's last state
+    let sizes: Vec<usize> = tiers
+        .iter()
+        .map(|(table, _)| table.odds.len() + 1)
+        .collect();
+    // Each tier also has a state for not having dropped yet, whose treasure opening is known from the number of boxes
+    // opened so far. That keeps track of which tiers have dropped without tracking every combination of them on top.
+    let states = sizes
+        .iter()
+        .try_fold(1usize, |states, &size| states.checked_mul(size + 1))
+        .filter(|&states| states <= MAX_STATES)
+        .ok_or(Error::TooManyStates(MAX_STATES))?;
+    let chances: Vec<Vec<f64>> = tiers
+        .iter()
+        .zip(&sizes)
+        .map(|((table, _), &size)| {
+            table
+                .odds_from(1)
+                .take(size)
+                .map(|odds| 1. / odds)
+                .collect()
+        })
+        .collect();
+
+    // The rarest tier is the one that takes longest to get from the first opening
+    let mut rarest_first: Vec<usize> = (0..tiers.len()).collect();
+    let expected = tiers
+        .iter()
+        .map(|(table, _)| expected_value::<f64>(table, 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    rarest_first.sort_by(|&a, &b| expected[b].total_cmp(&expected[a]));
+
+    // A state is a digit for each tier, written as one number: the tier's treasure opening (less 1) since it last
+    // dropped, or its size if it hasn't dropped yet
+    let decode = |mut state: usize| -> Vec<usize> {
+        sizes
+            .iter()
+            .map(|size| {
+                let digit = state % (size + 1);
+                state /= size + 1;
+                digit
+            })
+            .collect()
+    };
+    let encode = |digits: &[usize]| -> usize {
+        digits
+            .iter()
+            .zip(&sizes)
+            .rev()
+            .fold(0, |state, (digit, size)| state * (size + 1) + digit)
+    };
+    let start: Vec<usize> = tiers
+        .iter()
+        .zip(&sizes)
+        .map(|((_, opening), size)| (opening - 1).min(size - 1))
+        .collect();
+
+    // For a state after `boxes` boxes, the chance of each tier dropping (in the order the tiers were given) and then
+    // of a common item, along with the state each of those outcomes leads to
+    let transitions = |state: usize, boxes: usize| -> Vec<(f64, usize)> {
+        let digits = decode(state);
+        let openings: Vec<usize> = digits
+            .iter()
+            .zip(&sizes)
+            .zip(&start)
+            .map(|((&digit, &size), &start)| match digit == size {
+                true => (start + boxes).min(size - 1),
+                false => digit,
+            })
+            .collect();
+        let ordered: Vec<f64> = rarest_first
+            .iter()
+            .map(|&t| chances[t][openings[t]])
+            .collect();
+        let ordered = outcome_probabilities(&ordered, overlap);
+        let mut outcomes = vec![(0., 0); tiers.len() + 1];
+        for (&t, &prob) in rarest_first.iter().zip(&ordered) {
+            outcomes[t].0 = prob;
+        }
+        outcomes[tiers.len()].0 = ordered[tiers.len()];
+
+        let advanced: Vec<usize> = digits
+            .iter()
+            .zip(&sizes)
+            .map(|(&digit, &size)| match digit == size {
+                true => digit,
+                false => (digit + 1).min(size - 1),
+            })
+            .collect();
+        for (t, outcome) in outcomes.iter_mut().enumerate() {
+            let mut next = advanced.clone();
+            if t < tiers.len() {
+                next[t] = 0;
+            }
+            outcome.1 = encode(&next);
+        }
+        outcomes
+    };
+
+    // Once every tier that hasn't dropped is past the end of its table, the transitions stop depending on the number
+    // of boxes opened, so they're worked out once for every state rather than for each box
+    let settled = sizes
+        .iter()
+        .zip(&start)
+        .map(|(size, start)| size - 1 - start)
+        .max()
+        .unwrap_or(0);
+    let mut settled_transitions: Vec<Vec<(f64, usize)>> = Vec::new();
+
+    let none_dropped = encode(&sizes);
+    let mut probabilities = vec![0.; states];
+    probabilities[none_dropped] = 1.;
+
+    let mut expected_drops = vec![0.; tiers.len()];
+    let mut expected_common = 0.;
+    for boxes in 0..num_boxes {
+        if boxes >= settled && settled_transitions.is_empty() {
+            settled_transitions = (0..states).map(|state| transitions(state, boxes)).collect();
+        }
+        let mut next = vec![0.; states];
+        for (state, &prob) in probabilities.iter().enumerate() {
+            if prob == 0. {
+                continue;
+            }
+            let unsettled;
+            let outcomes = match settled_transitions.get(state) {
+                Some(outcomes) => outcomes,
+                None => {
+                    unsettled = transitions(state, boxes);
+                    &unsettled
+                }
+            };
+            for (t, &(outcome, next_state)) in outcomes.iter().enumerate() {
+                match expected_drops.get_mut(t) {
+                    Some(drops) => *drops += prob * outcome,
+                    None => expected_common += prob * outcome,
+                }
+                next[next_state] += prob * outcome;
+            }
+        }
+        probabilities = next;
+    }
+
+    let all = (0..states)
+        .filter(|&state| {
+            decode(state)
+                .iter()
+                .zip(&sizes)
+                .all(|(digit, size)| digit != size)
+        })
+        .map(|state| probabilities[state])
+        .sum();
+    Ok(JointResult {
+        boxes: num_boxes,
+        all,
+        any: 1. - probabilities[none_dropped],
+        expected_drops,
+        expected_common,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc::probability, renewal, Rarity, Tail};
+
+    fn table(name: &str, odds: f64) -> OddsTable {
+        OddsTable {
+            name: name.to_owned(),
+            odds: vec![odds],
+            tail: Tail::RepeatLast,
+        }
+    }
+
+    #[test]
+    fn one_tier_is_the_single_item_calculation() {
+        let rare = Rarity::Rare.table();
+        let result = joint(&[(&rare, 3)], Overlap::RarestFirst, 40).unwrap();
+        let expected: f64 = probability(&rare, 3, 40).unwrap();
+        assert!((result.any - expected).abs() < 1e-12);
+        assert!((result.all - expected).abs() < 1e-12);
+        let drops = renewal::expected_drops(&renewal::drops_distribution(&rare, 3, 40).unwrap());
+        assert!((result.expected_drops[0] - drops).abs() < 1e-12);
+        assert!((result.expected_drops[0] + result.expected_common - 40.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rarest_first_gives_the_rarest_tier_its_full_chance() {
+        let outcomes = outcome_probabilities(&[0.7, 0.6], Overlap::RarestFirst);
+        assert_eq!(outcomes.len(), 3);
+        assert!((outcomes[0] - 0.7).abs() < 1e-12);
+        assert!((outcomes[1] - 0.3).abs() < 1e-12);
+        assert_eq!(outcomes[2], 0.);
+    }
+
+    #[test]
+    fn normalize_scales_every_tier_down() {
+        let outcomes = outcome_probabilities(&[0.75, 0.5], Overlap::Normalize);
+        assert!((outcomes[0] - 0.6).abs() < 1e-12);
+        assert!((outcomes[1] - 0.4).abs() < 1e-12);
+        assert_eq!(outcomes[2], 0.);
+        // Chances that fit in one box are left alone
+        assert_eq!(
+            outcome_probabilities(&[0.25, 0.5], Overlap::Normalize),
+            [0.25, 0.5, 0.25]
+        );
+    }
+
+    #[test]
+    fn all_and_any_of_constant_tiers() {
+        // The tiers never overlap, so each box drops tier a with a chance of 1/4, tier b with 1/2 and a common item
+        // with 1/4
+        let (a, b) = (table("a", 4.), table("b", 2.));
+        let n = 5;
+        let result = joint(&[(&a, 1), (&b, 1)], Overlap::RarestFirst, n).unwrap();
+        let none = |chance: f64| (1. - chance).powi(n as i32);
+        assert!((result.any - (1. - none(0.75))).abs() < 1e-12);
+        assert!((result.all - (1. - none(0.25) - none(0.5) + none(0.75))).abs() < 1e-12);
+        assert!((result.expected_drops[0] - 1.25).abs() < 1e-12);
+        assert!((result.expected_drops[1] - 2.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn every_builtin_rarity_together() {
+        let tables: Vec<_> = Rarity::ALL.iter().map(|rarity| rarity.table()).collect();
+        let tiers: Vec<_> = tables.iter().zip([3, 7, 20]).collect();
+        let result = joint(&tiers, Overlap::RarestFirst, 60).unwrap();
+        assert!(0. < result.all && result.all <= result.any && result.any <= 1.);
+        let total = result.expected_drops.iter().sum::<f64>() + result.expected_common;
+        assert!((total - 60.).abs() < 1e-9);
+        // Sharing boxes with the other tiers can only make each tier less likely than on its own
+        for ((table, opening), drops) in tiers.iter().zip(&result.expected_drops) {
+            let alone =
+                renewal::expected_drops(&renewal::drops_distribution(table, *opening, 60).unwrap());
+            assert!(*drops <= alone + 1e-9);
+        }
+    }
+
+    #[test]
+    fn too_many_tiers_are_an_error() {
+        let tables: Vec<_> = (0..20).map(|i| table(&format!("tier-{i}"), 4.)).collect();
+        let tiers: Vec<_> = tables.iter().map(|table| (table, 1)).collect();
+        assert!(matches!(
+            joint(&tiers, Overlap::Normalize, 1),
+            Err(Error::TooManyStates(MAX_STATES))
+        ));
+    }
+
+    #[test]
+    fn duplicate_tiers_are_an_error() {
+        let rare = Rarity::Rare.table();
+        assert!(matches!(
+            joint(&[(&rare, 1), (&rare, 2)], Overlap::Normalize, 1),
+            Err(Error::DuplicateTier(_))
+        ));
+    }
+}
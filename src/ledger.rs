@@ -0,0 +1,186 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{treasure::Treasure, Error};
+
+/// The file the ledger is kept in when no other is given, in the home directory
+pub const DEFAULT_LEDGER: &str = ".dota-odds-ledger.json";
+
+/// One box that was opened
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Opened {
+    pub treasure: String,
+    /// The rarity of the item the box dropped, if it was one of the rarities with escalating odds
+    #[serde(default)]
+    pub got: Option<String>,
+    /// When the box was recorded, in seconds since the Unix epoch
+    #[serde(default)]
+    pub time: u64,
+}
+
+/// Every box recorded so far, oldest first. The treasure opening of each rarity is worked out from these, since it's
+/// the number of boxes opened since that rarity last dropped.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Ledger {
+    pub boxes: Vec<Opened>,
+}
+
+impl Ledger {
+    /// Where the ledger is kept when no other file is given, which needs a home directory
+    pub fn default_path() -> Result<PathBuf, Error> {
+        std::env::home_dir()
+            .filter(|home| !home.as_os_str().is_empty())
+            .map(|home| home.join(DEFAULT_LEDGER))
+            .ok_or_else(|| {
+                Error::Ledger(
+                    "there's no home directory to keep it in, pass --ledger <file>".to_owned(),
+                )
+            })
+    }
+
+    /// Read a ledger, or start an empty one if the file doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Ledger::default()),
+            Err(e) => return Err(Error::Ledger(format!("{}: {e}", path.display()))),
+        };
+        serde_json::from_str(&contents)
+            .map_err(|e| Error::Ledger(format!("{}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Ledger(format!("{}: {e}", path.display())))?;
+        fs::write(path, contents + "\n")
+            .map_err(|e| Error::Ledger(format!("{}: {e}", path.display())))
+    }
+
+    /// Record a box of `treasure`, and the rarity it dropped if any. The rarity has to be one the treasure can drop.
+    pub fn record(&mut self, treasure: &Treasure, got: Option<&str>) -> Result<(), Error> {
+        if let Some(rarity) = got {
+            treasure.table(rarity)?;
+        }
+        self.boxes.push(Opened {
+            treasure: treasure.name.clone(),
+            got: got.map(str::to_owned),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+        });
+        Ok(())
+    }
+
+    /// The boxes of one treasure, oldest first
+    pub fn boxes<'a>(&'a self, treasure: &'a str) -> impl Iterator<Item = &'a Opened> + 'a {
+        self.boxes
+            .iter()
+            .filter(move |opened| opened.treasure == treasure)
+    }
+
+    /// The treasure opening the next box of `treasure` is on for `rarity`: 1 more than the number of boxes opened since
+    /// the rarity last dropped, or since the first recorded box if it never has
+    pub fn treasure_opening(&self, treasure: &str, rarity: &str) -> usize {
+        self.boxes
+            .iter()
+            .rev()
+            .filter(|opened| opened.treasure == treasure)
+            .take_while(|opened| opened.got.as_deref() != Some(rarity))
+            .count()
+            + 1
+    }
+
+    /// The treasures with at least one recorded box, in the order they were first recorded
+    pub fn treasures(&self) -> Vec<&str> {
+        let mut treasures: Vec<&str> = Vec::new();
+        for opened in &self.boxes {
+            if !treasures.contains(&opened.treasure.as_str()) {
+                treasures.push(&opened.treasure);
+            }
+        }
+        treasures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::treasure::{Treasures, DEFAULT_TREASURE};
+
+    /// A ledger of boxes of the built-in treasure, each dropping the rarity given or nothing
+    fn ledger(boxes: &[Option<&str>]) -> Ledger {
+        let treasures = Treasures::builtin();
+        let treasure = treasures.treasure(DEFAULT_TREASURE).unwrap();
+        let mut ledger = Ledger::default();
+        for got in boxes {
+            ledger.record(treasure, *got).unwrap();
+        }
+        ledger
+    }
+
+    #[test]
+    fn openings_count_the_boxes_since_the_last_drop() {
+        let opening = |ledger: &Ledger, rarity| ledger.treasure_opening(DEFAULT_TREASURE, rarity);
+        assert_eq!(opening(&ledger(&[]), "rare"), 1);
+
+        let boxes = ledger(&[None, None, Some("rare"), None, Some("ultra-rare"), None]);
+        // Dropping a rarity takes it back to 1, and every box after it counts
+        assert_eq!(opening(&boxes, "rare"), 4);
+        assert_eq!(opening(&boxes, "ultra-rare"), 2);
+        // A rarity that never dropped counts every box
+        assert_eq!(opening(&boxes, "very-rare"), 7);
+        // The last box dropping the rarity puts it back at 1
+        assert_eq!(opening(&ledger(&[None, Some("rare")]), "rare"), 1);
+    }
+
+    #[test]
+    fn other_treasures_are_counted_separately() {
+        let mut ledger = ledger(&[None, None]);
+        ledger.boxes.push(Opened {
+            treasure: "cache".to_owned(),
+            got: None,
+            time: 0,
+        });
+        assert_eq!(ledger.treasure_opening(DEFAULT_TREASURE, "rare"), 3);
+        assert_eq!(ledger.treasure_opening("cache", "rare"), 2);
+        assert_eq!(ledger.treasures(), [DEFAULT_TREASURE, "cache"]);
+        assert_eq!(ledger.boxes("cache").count(), 1);
+    }
+
+    #[test]
+    fn only_rarities_of_the_treasure_can_be_recorded() {
+        let treasures = Treasures::builtin();
+        let mut ledger = Ledger::default();
+        assert!(matches!(
+            ledger.record(
+                treasures.treasure(DEFAULT_TREASURE).unwrap(),
+                Some("mythical")
+            ),
+            Err(Error::UnknownRarity { .. })
+        ));
+        assert!(ledger.boxes.is_empty());
+    }
+
+    #[test]
+    fn ledgers_are_saved_and_loaded() {
+        let dir =
+            std::env::temp_dir().join(format!("dota-odds-calc-ledger-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ledger.json");
+        // A ledger that doesn't exist yet is empty
+        assert!(Ledger::load(&path).unwrap().boxes.is_empty());
+
+        let ledger = ledger(&[None, Some("very-rare")]);
+        ledger.save(&path).unwrap();
+        assert_eq!(Ledger::load(&path).unwrap().boxes, ledger.boxes);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(Ledger::load(&path), Err(Error::Ledger(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,31 @@
+//! Calculate how many boxes you need to open in Dota 2 to get the item you want, given the escalating odds of each
+//! treasure opening.
+
+pub mod calc;
+pub mod chart;
+pub mod cost;
+mod error;
+pub mod estimate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod goodness_of_fit;
+pub mod joint;
+pub mod ledger;
+pub mod multi;
+pub mod number;
+pub mod odds;
+pub mod plot;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod renewal;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sim;
+pub mod stats;
+pub mod treasure;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use calc::{distribution, expected_value, probability, quantile, summary};
+pub use error::Error;
+pub use odds::{OddsTable, OddsTables, Rarity, Tail, MAX_ODDS};
@@ -1,59 +1,188 @@
 use std::{error::Error, path::PathBuf};
 
-use clap::{Parser, ValueEnum};
-use csv::Writer;
-
-#[derive(ValueEnum, Clone, Debug)]
-enum Rarity {
-    Rare,
-    VeryRare,
-    UltraRare,
-}
+use clap::Parser;
 
-const MAX_ODDS: usize = 50;
-
-impl Rarity {
-    fn odds(&self) -> &[f32; MAX_ODDS] {
-        match self {
-            Rarity::Rare => &[
-                20_000., 583., 187., 88., 51., 33., 23., 17., 13.1, 10.4, 8.5, 7.1, 6.0, 5.2, 4.5,
-                4.0, 3.6, 3.2, 2.9, 2.6, 2.4, 2.2, 2.1, 1.9, 1.8, 1.7, 1.6, 1.5, 1.5, 1.4, 1.3,
-                1.3, 1.2, 1.2, 1.2, 1.1, 1.1, 1.1, 1.1, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
-                1.0, 1.0, 1.0,
-            ],
-            Rarity::VeryRare => &[
-                20_000., 3_653., 1_059., 485., 276., 178., 124., 92., 70., 56., 45., 38., 32., 27.,
-                24., 21., 18., 16., 14.1, 12.7, 11.5, 10.5, 9.6, 8.8, 8.1, 7.5, 7.0, 6.5, 6.0, 5.7,
-                5.3, 5.0, 4.7, 4.5, 4.2, 4.0, 3.8, 3.6, 3.4, 3.3, 3.2, 3.0, 2.9, 2.8, 2.7, 2.6,
-                2.5, 2.4, 2.3, 2.2,
-            ],
-            Rarity::UltraRare => &[
-                100_000., 27_380., 8_614., 4_021., 2_303., 1_486., 1_037., 764., 586., 464., 376.,
-                311., 262., 223., 193., 168., 148., 131., 117., 105., 95., 86., 79., 72., 66., 61.,
-                57., 53., 49., 46., 43., 40., 38., 35., 33., 32., 30., 28., 27., 26., 24., 23.,
-                22., 21., 20., 19., 19., 18., 17., 17.,
-            ],
-        }
-    }
-}
+mod dashboard;
+mod output;
+mod repl;
+
+use dota_odds_calc::{
+    chart::{chart, multi_chart, Axis, ChartSpec, Quantity},
+    cost::{Bundle, Pricing},
+    distribution, estimate, expected_value, goodness_of_fit,
+    joint::{joint, Overlap},
+    ledger::Ledger,
+    multi::{self, Goal, Target},
+    number::{Exact, Number},
+    odds::OddsFile,
+    probability, quantile, renewal,
+    server::Server,
+    sim, summary,
+    treasure::{Treasure, Treasures, DEFAULT_TREASURE},
+    OddsTable,
+};
+use output::{
+    goal_name, list_treasures, status, write_plots, Format, MultiOutput, Output, PlotFormat,
+};
 
 #[derive(clap::Subcommand, Debug)]
 enum Mode {
     /// Calculate the expected number of boxes you need to open to get the item you want
-    ExpectedValue,
+    ExpectedValue {
+        /// Also show the variance, standard deviation, median and 10th/90th percentiles of the number of boxes
+        #[arg(long)]
+        summary: bool,
+    },
     /// Calulcate the probability of opening the item you want after opening a number of boxes
     Probability {
         /// The number of boxes you will open
-        num_boxes: usize,
+        #[arg(required_unless_present = "budget")]
+        num_boxes: Option<usize>,
+        /// Instead of a number of boxes, open as many boxes as you can afford with this much money. Requires
+        /// --price-per-box.
+        #[arg(long, conflicts_with = "num_boxes")]
+        budget: Option<f64>,
     },
-    /// Produce a chart (.csv file) that shows the probabilities and expected values of several combinations of starting treasures and additional opened boxes
+    /// Produce a chart (.csv file) that shows the probabilities or expected values of every combination of the values
+    /// along its rows and columns
     Chart {
-        /// The maximum number of starting treasures to consider
-        max_treasures: usize,
-        /// The maximum number of boxes to consider purchasing for probability calculation
-        max_boxes: usize,
+        /// What each row is for, written as <quantity>=<values> with a quantity of opening, boxes, budget or rarity
+        /// (e.g. opening=30..50, boxes=10..200:10, boxes=1..1000:log20 or rarity=rare,ultra-rare). A plain number N
+        /// is short for opening=1..N.
+        #[arg(value_parser = |s: &str| Axis::parse(s, Quantity::Opening))]
+        rows: Axis,
+        /// What each column is for, written the same way as the rows. A plain number N is short for boxes=1..N.
+        #[arg(value_parser = |s: &str| Axis::parse(s, Quantity::Boxes))]
+        columns: Axis,
         /// The csv file to save expected value and probability information to
         out_file: PathBuf,
+        /// Chart getting several items at once instead of the rarity given before the mode, written as
+        /// <rarity>:<treasure opening>. Each opening of the chart is then a number of treasures opened past those
+        /// openings.
+        #[arg(long = "target")]
+        targets: Vec<Target>,
+        /// Also plot the chart as an image, saved next to the chart file as <chart>-probability.<ext> and
+        /// <chart>-expected-value.<ext>. Can be given more than once.
+        #[arg(long, value_enum)]
+        plot: Vec<PlotFormat>,
+    },
+    /// Show the probability of getting the item in exactly, and within, each number of boxes up to a maximum
+    Distribution {
+        /// The maximum number of boxes to show
+        num_boxes: usize,
+    },
+    /// Calculate the probability of getting each number of items in a run of boxes, counting every copy rather than
+    /// stopping at the first. The treasure opening goes back to 1 after each item.
+    Drops {
+        /// The number of boxes you will open, up to 5000
+        num_boxes: usize,
+    },
+    /// Calculate how many boxes you need to open to be a certain percent sure of getting the item you want
+    Quantile {
+        /// The confidence levels to calculate, between 0 and 1 (e.g. 0.9 to be 90% sure)
+        #[arg(required = true)]
+        levels: Vec<f64>,
+    },
+    /// Calculate the expected number of boxes and the probability of getting all of, or any of, several items at once.
+    /// Each item keeps its own escalating odds. Doesn't use the rarity given before the mode.
+    Multi {
+        /// The items you're trying to open, written as <rarity>:<treasure opening> (e.g. ultra-rare:12)
+        #[arg(long = "target", required = true)]
+        targets: Vec<Target>,
+        /// The number of boxes you will open, to calculate the probability of getting the items
+        num_boxes: Option<usize>,
+    },
+    /// Calculate what to expect from a run of boxes when every box drops exactly one item: one of several rarities, or
+    /// a common item. Each rarity keeps its own treasure opening. Doesn't use the rarity given before the mode.
+    Joint {
+        /// The rarities the boxes can drop, written as <rarity>:<treasure opening> (e.g. ultra-rare:12)
+        #[arg(long = "tier", required = true)]
+        tiers: Vec<Target>,
+        /// The number of boxes you will open
+        num_boxes: usize,
+        /// What to do when the rarities' chances for a box add up to more than 1: rarest-first lets rarer rarities
+        /// keep their full chance, normalize scales every chance down by the same amount
+        #[arg(long, default_value = "rarest-first")]
+        overlap: Overlap,
+    },
+    /// Open boxes at random many times over to check the expected value and probabilities calculated by the other modes
+    Simulate {
+        /// The maximum number of boxes to compare probabilities for. Defaults to the number of boxes needed to be 99%
+        /// sure of getting the item.
+        max_boxes: Option<usize>,
+        /// The number of times to simulate opening boxes until the item drops
+        #[arg(long, default_value = "100000")]
+        trials: usize,
+        /// The seed for the random number generator. The same seed always gives the same results.
+        #[arg(long, default_value = "0")]
+        seed: u64,
+    },
+    /// List the treasures the calculator knows about, along with the rarities they can drop
+    ListTreasures,
+    /// Explore the odds at a prompt, changing the rarity, treasure opening and price between questions instead of
+    /// running the calculator again. The rarity, treasure opening and options given before the mode are where the
+    /// prompt starts.
+    Interactive,
+    /// Show a full-screen dashboard of the expected value, probability and odds, changing the treasure opening with the
+    /// left and right arrow keys and the number of boxes with the up and down arrow keys
+    Dashboard {
+        /// The number of boxes to start with
+        #[arg(default_value = "10")]
+        num_boxes: usize,
+    },
+    /// Run a local HTTP server that answers /expected-value, /probability and /chart requests with JSON, for the
+    /// treasures given by --odds-file and the built-in one
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Record the boxes you open in the ledger, so the treasure opening of each rarity is kept track of for you
+    Record {
+        #[command(subcommand)]
+        action: RecordAction,
+    },
+    /// Estimate the drop probability of each opening from a log of opened boxes, and compare it with the odds table of
+    /// the rarity given before the mode
+    Estimate {
+        /// A CSV file with an opening,result header and a row for each box: the treasure opening it was on and the rarity
+        /// it dropped (anything else, such as none, for boxes that didn't drop the rarity)
+        log: PathBuf,
+        /// Don't let the fitted drop probability go down from one opening to the next
+        #[arg(long)]
+        monotone: bool,
+        /// How much of the posterior the credible intervals hold, between 0 and 1
+        #[arg(long, default_value = "0.95")]
+        credible: f64,
+        /// Save the fitted odds as an odds file (TOML, or JSON if the file ends in .json) that --odds-file can load
+        #[arg(long)]
+        export: Option<PathBuf>,
+        /// The name of the exported table. Defaults to the rarity followed by -fitted.
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Test whether a log of opened boxes is consistent with the odds table of the rarity given before the mode, and show
+    /// the openings that deviate most from it
+    GoodnessOfFit {
+        /// A CSV file with an opening,result header and a row for each box, in the same format as for estimate
+        log: PathBuf,
+        /// How many of the most deviating openings to show
+        #[arg(long, default_value = "5")]
+        top: usize,
+    },
+    /// Show the treasure opening each rarity is on for every treasure in the ledger, with the odds of the next box and
+    /// the expected number of boxes still to open
+    Status,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum RecordAction {
+    /// Record opening a box of the treasure given by --treasure
+    Open {
+        /// The rarity of the item the box dropped, if it was one (rare, very-rare, ultra-rare, or a table from an odds
+        /// file). Leave it out if the box dropped anything else.
+        #[arg(long)]
+        got: Option<String>,
     },
 }
 
@@ -62,114 +191,429 @@ struct Args {
     #[command(subcommand)]
     mode: Mode,
 
-    /// The rarity of the item you're trying to open
-    rarity: Rarity,
+    /// The rarity of the item you're trying to open (rare, very-rare, ultra-rare, or a table from an odds file)
+    rarity: Option<String>,
+
+    /// The treasure opening that you're on (should be highlighted by the Dota client). Min 1. Defaults to the opening
+    /// worked out from the boxes recorded in the ledger, or 1 if none have been recorded.
+    treasure_opening: Option<usize>,
+
+    /// The treasure you're opening. Only the standard treasure is built in, others come from --odds-file. Run
+    /// list-treasures to see the available treasures.
+    #[arg(long, global = true, default_value = DEFAULT_TREASURE)]
+    treasure: String,
+
+    /// The ledger file that record and status keep opened boxes in, and that treasure openings are worked out from.
+    /// Defaults to .dota-odds-ledger.json in the home directory.
+    #[arg(long, global = true)]
+    ledger: Option<PathBuf>,
+
+    /// A TOML or JSON file of odds tables to use alongside (or in place of) the built-in ones. Can be given more than
+    /// once. See README.md for the format.
+    #[arg(long)]
+    odds_file: Vec<PathBuf>,
+
+    /// The price of a single box. When given, expected spend and costs are shown alongside numbers of boxes.
+    #[arg(long)]
+    price_per_box: Option<f64>,
+
+    /// A pack of boxes sold at a discount, written as <boxes>for<paid> (e.g. 11for10 for 11 boxes at the price of 10).
+    /// Can be given more than once.
+    #[arg(long)]
+    bundle: Vec<Bundle>,
+
+    /// The currency symbol to print before amounts of money
+    #[arg(long, default_value = "$")]
+    currency: String,
 
-    /// The treasure opening that you're on (should be highlighted by the Dota client). Min 1.
-    #[arg(default_value = "1")]
-    treasure_opening: usize,
+    /// Calculate exactly, with fractions of big integers instead of floating point numbers, for a reference answer.
+    /// Slower, and only for expected-value, probability, distribution and quantile.
+    #[arg(long)]
+    exact: bool,
+
+    /// Round calculated probabilities, expected values and statistics to this many significant digits. Exact results
+    /// are written to 30 significant digits unless this is given.
+    #[arg(long, global = true, value_name = "DIGITS")]
+    precision: Option<usize>,
+
+    /// How to print results. json and csv records include the inputs each result was calculated from. For chart, this
+    /// is the format of the chart file.
+    #[arg(long, value_enum, global = true, default_value = "text")]
+    format: Format,
 }
 
 fn main() {
-    let args = Args::parse();
-
-    if args.treasure_opening < 1 {
-        println!("Treasure opening must be 1 or greater");
-    } else {
-        match args.mode {
-            Mode::ExpectedValue => {
-                let exp = expected_value(&args.rarity, args.treasure_opening);
-                println!("{}", exp)
-            }
-            Mode::Probability { num_boxes } => {
-                let prob = probability(&args.rarity, args.treasure_opening, num_boxes);
-                println!("{}", prob);
-            }
-            Mode::Chart {
-                max_treasures,
-                max_boxes,
-                out_file,
-            } => {
-                chart(args.rarity, max_treasures, max_boxes, &out_file).unwrap();
-            }
-        }
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("{e}");
+        std::process::exit(1);
     }
 }
 
-fn expected_value(rarity: &Rarity, treasure_opening: usize) -> f32 {
-    // The probability that we make it to this point
-    let mut cum_prob = 1.;
-    // Expected value
-    let mut exp = 0.;
-    rarity
-        .odds()
-        .iter()
-        .enumerate()
-        .skip(treasure_opening - 1)
-        .for_each(|(i, p)| {
-            // The probability of the ith chest being the next one we open is the probability of getting to the ith chest
-            // times the probability of opening that chest (1 / p)
-            let p = 1. / p;
-            exp += ((i + 1) - (treasure_opening - 1)) as f32 * cum_prob * p;
-
-            // Then the probability we make it to the next chest is the probability we made it to this chest times the
-            // probability we didn't open this chest
-            cum_prob *= 1. - p;
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    if args.exact
+        && !matches!(
+            args.mode,
+            Mode::ExpectedValue { .. }
+                | Mode::Probability { .. }
+                | Mode::Distribution { .. }
+                | Mode::Quantile { .. }
+        )
+    {
+        return Err(
+            "--exact only works with expected-value, probability, distribution and quantile".into(),
+        );
+    }
+
+    let mut treasures = Treasures::builtin();
+    for odds_file in &args.odds_file {
+        treasures.add_file(OddsFile::load(odds_file)?);
+    }
+
+    if let Mode::ListTreasures = args.mode {
+        return list_treasures(&treasures, args.format);
+    }
+    if let Mode::Serve { addr } = &args.mode {
+        let server = Server::bind(addr, treasures)?;
+        eprintln!("Listening on http://{}", server.addr());
+        server.run();
+        return Ok(());
+    }
+
+    let ledger_path = || args.ledger.clone().map_or_else(Ledger::default_path, Ok);
+    match &args.mode {
+        Mode::Status => {
+            return status(
+                &Ledger::load(&ledger_path()?)?,
+                &treasures,
+                args.format,
+                args.precision,
+            )
+        }
+        Mode::Record {
+            action: RecordAction::Open { got },
+        } => {
+            let ledger_path = ledger_path()?;
+            let mut ledger = Ledger::load(&ledger_path)?;
+            ledger.record(treasures.treasure(&args.treasure)?, got.as_deref())?;
+            ledger.save(&ledger_path)?;
+            return status(&ledger, &treasures, args.format, args.precision);
+        }
+        _ => {}
+    }
+
+    let treasure = treasures.treasure(&args.treasure)?;
+    // The treasure opening for a rarity, from the command line or else from the ledger. The ledger is only read when
+    // it's needed, so a missing home directory or a broken ledger doesn't get in the way of giving the opening.
+    let treasure_opening = |rarity: &str| -> Result<usize, dota_odds_calc::Error> {
+        match args.treasure_opening {
+            Some(treasure_opening) => Ok(treasure_opening),
+            None => Ok(Ledger::load(&ledger_path()?)?.treasure_opening(&treasure.name, rarity)),
+        }
+    };
+
+    if let Mode::Interactive = args.mode {
+        if let Some(rarity) = &args.rarity {
+            treasure.table(rarity)?;
+        }
+        let pricing = args
+            .price_per_box
+            .map(|price_per_box| {
+                Pricing::new(price_per_box, args.bundle.clone(), args.currency.clone())
+            })
+            .transpose()?;
+        let treasure_opening = args
+            .rarity
+            .as_deref()
+            .map_or(Ok(args.treasure_opening.unwrap_or(1)), treasure_opening)?;
+        return repl::run(repl::Session {
+            treasures: &treasures,
+            treasure,
+            rarity: args.rarity,
+            treasure_opening,
+            pricing,
+            bundles: args.bundle,
+            currency: args.currency,
+            precision: args.precision,
         });
-    exp += if treasure_opening <= MAX_ODDS {
-        cum_prob * (rarity.odds().last().unwrap() + (MAX_ODDS - treasure_opening + 1) as f32)
-    } else {
-        *rarity.odds().last().unwrap()
+    }
+
+    match &args.mode {
+        Mode::Multi { targets, num_boxes } => {
+            let output = MultiOutput {
+                format: args.format,
+                treasure: &treasure.name,
+                targets: &describe_targets(targets),
+                precision: args.precision,
+            };
+            let targets = find_targets(treasure, targets)?;
+            let results = [Goal::All, Goal::Any]
+                .into_iter()
+                .map(|goal| {
+                    Ok((
+                        goal,
+                        multi::expected_value(goal, &targets)?,
+                        num_boxes
+                            .map(|num_boxes| multi::probability(goal, &targets, num_boxes))
+                            .transpose()?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, dota_odds_calc::Error>>()?;
+            return output.results(&results, *num_boxes);
+        }
+        Mode::Joint {
+            tiers,
+            num_boxes,
+            overlap,
+        } => {
+            let output = MultiOutput {
+                format: args.format,
+                treasure: &treasure.name,
+                targets: &describe_targets(tiers),
+                precision: args.precision,
+            };
+            let tables = find_targets(treasure, tiers)?;
+            let result = joint(&tables, *overlap, *num_boxes)?;
+            return output.joint(&result, tiers, *overlap);
+        }
+        Mode::Chart {
+            rows,
+            columns,
+            out_file,
+            targets,
+            plot,
+        } if !targets.is_empty() => {
+            let description = describe_targets(targets);
+            let output = MultiOutput {
+                format: args.format,
+                treasure: &treasure.name,
+                targets: &description,
+                precision: args.precision,
+            };
+            let targets = find_targets(treasure, targets)?;
+            let charts = [Goal::All, Goal::Any]
+                .into_iter()
+                .map(|goal| Ok((goal, multi_chart(goal, &targets, rows, columns)?)))
+                .collect::<Result<Vec<_>, dota_odds_calc::Error>>()?;
+            for (goal, chart) in &charts {
+                let goal = goal_name(*goal);
+                let title = format!("{goal} of {description} ({})", treasure.name);
+                write_plots(chart, &title, out_file, &format!("-{goal}"), plot)?;
+            }
+            return output.charts(&charts, out_file);
+        }
+        Mode::Chart {
+            rows,
+            columns,
+            out_file,
+            plot,
+            ..
+        } => {
+            // Charts with rarity on an axis don't need a rarity of their own
+            let swept = [rows, columns]
+                .iter()
+                .any(|axis| axis.quantity() == Quantity::Rarity);
+            let rarity = match (&args.rarity, swept) {
+                (None, false) => return Err("A rarity is required for this mode".into()),
+                (rarity, _) => rarity.as_deref(),
+            };
+            let pricing = args
+                .price_per_box
+                .map(|price_per_box| {
+                    Pricing::new(price_per_box, args.bundle.clone(), args.currency.clone())
+                })
+                .transpose()?;
+            let spec = ChartSpec {
+                rows: rows.clone(),
+                columns: columns.clone(),
+                rarity: rarity.map(str::to_owned),
+                treasure_opening: rarity
+                    .map_or(Ok(args.treasure_opening.unwrap_or(1)), treasure_opening)?,
+            };
+            let chart = chart(treasure, &spec, pricing.as_ref())?;
+            let output = Output {
+                format: args.format,
+                treasure: &treasure.name,
+                rarity: rarity.unwrap_or_default(),
+                treasure_opening: spec.treasure_opening,
+                pricing: pricing.as_ref(),
+                precision: args.precision,
+            };
+            output.chart(&chart, out_file)?;
+            let title = match rarity {
+                Some(rarity) => format!("{rarity} ({})", treasure.name),
+                None => treasure.name.clone(),
+            };
+            return write_plots(&chart, &title, out_file, "", plot);
+        }
+        _ => {}
+    }
+
+    let rarity = args
+        .rarity
+        .as_deref()
+        .ok_or("A rarity is required for this mode")?;
+    let table = treasure.table(rarity)?;
+    let treasure_opening = treasure_opening(rarity)?;
+
+    let pricing = args
+        .price_per_box
+        .map(|price_per_box| Pricing::new(price_per_box, args.bundle, args.currency))
+        .transpose()?;
+
+    let output = Output {
+        format: args.format,
+        treasure: &treasure.name,
+        rarity,
+        treasure_opening,
+        pricing: pricing.as_ref(),
+        precision: args.precision,
     };
 
-    exp
+    match args.mode {
+        Mode::ExpectedValue {
+            summary: show_summary,
+        } => {
+            if args.exact && show_summary {
+                return Err("--summary can't be calculated with --exact".into());
+            }
+            let summary = show_summary
+                .then(|| summary(table, treasure_opening))
+                .transpose()?;
+            let spend = pricing
+                .as_ref()
+                .map(|pricing| pricing.expected_spend(table, treasure_opening))
+                .transpose()?;
+            if args.exact {
+                let exp = expected_value::<Exact>(table, treasure_opening)?;
+                output.expected_value(exp, summary.as_ref(), spend)?;
+            } else {
+                let exp = expected_value::<f64>(table, treasure_opening)?;
+                output.expected_value(exp, summary.as_ref(), spend)?;
+            }
+        }
+        Mode::Probability { num_boxes, budget } => {
+            let num_boxes = match (num_boxes, budget, &pricing) {
+                (Some(num_boxes), _, _) => num_boxes,
+                (None, Some(budget), Some(pricing)) => pricing.boxes_within(budget)?,
+                (None, _, _) => return Err("--budget requires --price-per-box".into()),
+            };
+            if args.exact {
+                let prob = probability::<Exact>(table, treasure_opening, num_boxes)?;
+                output.probability(num_boxes, budget, prob)?;
+            } else {
+                let prob = probability::<f64>(table, treasure_opening, num_boxes)?;
+                output.probability(num_boxes, budget, prob)?;
+            }
+        }
+        Mode::Distribution { num_boxes } => {
+            if args.exact {
+                output.distribution(&distribution::<Exact>(table, treasure_opening, num_boxes)?)?;
+            } else {
+                output.distribution(&distribution::<f64>(table, treasure_opening, num_boxes)?)?;
+            }
+        }
+        Mode::Drops { num_boxes } => {
+            let exactly = renewal::drops_distribution(table, treasure_opening, num_boxes)?;
+            output.drops(
+                num_boxes,
+                renewal::expected_drops(&exactly),
+                &renewal::drops(&exactly),
+            )?;
+        }
+        Mode::Quantile { levels } => {
+            let quantiles = levels
+                .iter()
+                .map(|&level| {
+                    let boxes = match args.exact {
+                        true => quantile(table, treasure_opening, Exact::from_f64(level))?,
+                        false => quantile(table, treasure_opening, level)?,
+                    };
+                    Ok((level, boxes))
+                })
+                .collect::<Result<Vec<_>, dota_odds_calc::Error>>()?;
+            output.quantiles(&quantiles)?;
+        }
+        Mode::Simulate {
+            max_boxes,
+            trials,
+            seed,
+        } => {
+            let max_boxes = match max_boxes {
+                Some(max_boxes) => max_boxes,
+                None => quantile(table, treasure_opening, 0.99)?.unwrap(),
+            };
+            let boxes = sim::simulate(table, treasure_opening, trials, seed)?;
+            let probabilities = (1..=max_boxes)
+                .map(|n| probability(table, treasure_opening, n))
+                .collect::<Result<Vec<_>, _>>()?;
+            output.simulation(
+                trials,
+                seed,
+                &sim::mean(&boxes),
+                expected_value(table, treasure_opening)?,
+                &sim::cumulative_fractions(&boxes, &probabilities),
+                &probabilities,
+            )?;
+        }
+        Mode::Dashboard { num_boxes } => {
+            let dashboard =
+                dashboard::Dashboard::new(treasure, rarity, treasure_opening, num_boxes)?;
+            ratatui::run(|terminal| dashboard.run(terminal))?;
+        }
+        Mode::Estimate {
+            log,
+            monotone,
+            credible,
+            export,
+            name,
+        } => {
+            let fits = estimate::fit(
+                &estimate::count(&estimate::read_log(&log)?, rarity),
+                monotone,
+                credible,
+            )?;
+            output.estimate(credible, monotone, &fits, table)?;
+            if let Some(export) = export {
+                let name = name.unwrap_or_else(|| format!("{rarity}-fitted"));
+                OddsFile {
+                    treasure: Some(treasure.name.clone()),
+                    description: None,
+                    tables: vec![estimate::fitted_table(&fits, &name)],
+                }
+                .save(&export)?;
+            }
+        }
+        Mode::GoodnessOfFit { log, top } => {
+            let counts = estimate::count(&estimate::read_log(&log)?, rarity);
+            output.goodness_of_fit(&goodness_of_fit::test(&counts, table)?, top)?;
+        }
+        Mode::ListTreasures
+        | Mode::Multi { .. }
+        | Mode::Joint { .. }
+        | Mode::Chart { .. }
+        | Mode::Interactive
+        | Mode::Serve { .. }
+        | Mode::Record { .. }
+        | Mode::Status => unreachable!(),
+    }
+    Ok(())
 }
 
-fn probability(rarity: &Rarity, treasure_opening: usize, num_boxes: usize) -> f32 {
-    rarity
-        .odds()
+/// Write targets the same way they're written on the command line
+fn describe_targets(targets: &[Target]) -> String {
+    targets
         .iter()
-        .chain(std::iter::repeat(rarity.odds().last().unwrap()))
-        .skip(treasure_opening - 1)
-        .take(num_boxes)
-        .scan(1., |cum_prob, p| {
-            // The probability of the ith chest being the next one we open is the probability of getting to the ith chest
-            // times the probability of opening that chest (1 / p)
-            let p = 1. / p;
-            let prob = *cum_prob * p;
-
-            // Then the probability we make it to the next chest is the probability we made it to this chest times the
-            // probability we didn't open this chest
-            *cum_prob *= 1. - p;
-
-            Some(prob)
-        })
-        .sum()
+        .map(|target| format!("{}:{}", target.rarity, target.treasure_opening))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-fn chart(
-    rarity: Rarity,
-    max_treasures: usize,
-    max_boxes: usize,
-    out: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
-    let mut wtr = Writer::from_path(out)?;
-
-    wtr.write_record(
-        std::iter::repeat(String::new())
-            .take(3)
-            .chain((1..=max_boxes).map(|n| n.to_string())),
-    )?;
-
-    for treasures in 1..=max_treasures {
-        let exp = expected_value(&rarity, treasures);
-        wtr.write_record(
-            [treasures.to_string(), exp.to_string(), String::new()]
-                .into_iter()
-                .chain(
-                    (1..=max_boxes).map(|boxes| probability(&rarity, treasures, boxes).to_string()),
-                ),
-        )?;
-    }
-    Ok(())
+fn find_targets<'a>(
+    treasure: &'a Treasure,
+    targets: &[Target],
+) -> Result<Vec<(&'a OddsTable, usize)>, dota_odds_calc::Error> {
+    targets
+        .iter()
+        .map(|target| Ok((treasure.table(&target.rarity)?, target.treasure_opening)))
+        .collect()
 }
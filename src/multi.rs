@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use crate::{odds::OddsTable, Error};
+
+/// An item we're trying to open, written on the command line as `<rarity>:<treasure opening>` (e.g. `ultra-rare:12`).
+/// The treasure opening can be left off to start at 1.
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub rarity: String,
+    pub treasure_opening: usize,
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidTarget(s.to_owned());
+
+        let (rarity, treasure_opening) = match s.split_once(':') {
+            Some((rarity, opening)) => (rarity, opening.parse().map_err(|_| invalid())?),
+            None => (s, 1),
+        };
+        if rarity.is_empty() || treasure_opening < 1 {
+            return Err(invalid());
+        }
+
+        Ok(Target {
+            rarity: rarity.to_owned(),
+            treasure_opening,
+        })
+    }
+}
+
+/// Whether we want every target item, or are happy with whichever one comes first
+#[derive(Clone, Copy, Debug)]
+pub enum Goal {
+    All,
+    Any,
+}
+
+/// The probability of not yet having each target, before opening any boxes and then after each box. Every target
+/// has its own escalating odds, so each one's chance only depends on how many boxes have been opened.
+fn survivals<'a>(targets: &'a [(&'a OddsTable, usize)]) -> impl Iterator<Item = Vec<f64>> + 'a {
+    let mut odds: Vec<_> = targets
+        .iter()
+        .map(|(table, treasure_opening)| table.odds_from(*treasure_opening))
+        .collect();
+    std::iter::successors(Some(vec![1.; targets.len()]), move |survival| {
+        Some(
+            survival
+                .iter()
+                .zip(&mut odds)
+                .map(|(s, odds)| s * (1. - 1. / odds.next().unwrap()))
+                .collect(),
+        )
+    })
+}
+
+/// The probability that we haven't reached the goal, given the probability of not yet having each target
+fn goal_survival(goal: Goal, survival: &[f64]) -> f64 {
+    match goal {
+        // We're missing at least one target
+        Goal::All => 1. - survival.iter().map(|s| 1. - s).product::<f64>(),
+        // We're missing every target
+        Goal::Any => survival.iter().product(),
+    }
+}
+
+pub(crate) fn check(targets: &[(&OddsTable, usize)]) -> Result<(), Error> {
+    if targets.is_empty() {
+        return Err(Error::NoTargets);
+    }
+    targets
+        .iter()
+        .try_for_each(|(table, treasure_opening)| table.check(*treasure_opening))
+}
+
+/// The probability of reaching the goal within `num_boxes` boxes
+pub fn probability(
+    goal: Goal,
+    targets: &[(&OddsTable, usize)],
+    num_boxes: usize,
+) -> Result<f64, Error> {
+    check(targets)?;
+    Ok(1. - goal_survival(goal, &survivals(targets).nth(num_boxes).unwrap()))
+}
+
+/// The expected number of boxes needed to reach the goal
+pub fn expected_value(goal: Goal, targets: &[(&OddsTable, usize)]) -> Result<f64, Error> {
+    check(targets)?;
+
+    // Past the end of every target's table, each target's chance of still being missing shrinks by at least a factor
+    // of 1 - 1 / `slowest` with every box. The goal is missed only while some target is, so the rest of the sum is at
+    // most the targets' chances of still being missing, added up, times `slowest`.
+    let slowest = targets
+        .iter()
+        .map(|(table, _)| table.tail_odds())
+        .fold(1., f64::max);
+    let table_boxes = targets
+        .iter()
+        .map(|(table, treasure_opening)| (table.odds.len() + 1).saturating_sub(*treasure_opening))
+        .max()
+        .unwrap_or(0);
+
+    // The expected number of boxes is the sum over n of the probability that n boxes weren't enough
+    let mut expected = 0.;
+    for (boxes, survival) in survivals(targets).enumerate() {
+        let rest = survival.iter().sum::<f64>() * slowest;
+        if boxes >= table_boxes && rest <= f64::EPSILON * expected {
+            break;
+        }
+        expected += goal_survival(goal, &survival);
+    }
+    Ok(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc, Rarity};
+
+    #[test]
+    fn one_target_is_the_single_item_calculation() {
+        for rarity in Rarity::ALL {
+            let table = rarity.table();
+            let single: f64 = calc::expected_value(&table, 4).unwrap();
+            for goal in [Goal::All, Goal::Any] {
+                let multi = expected_value(goal, &[(&table, 4)]).unwrap();
+                assert!((multi - single).abs() < 1e-12 * single, "{multi} {single}");
+                let single: f64 = calc::probability(&table, 4, 30).unwrap();
+                let multi = probability(goal, &[(&table, 4)], 30).unwrap();
+                assert!((multi - single).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn all_takes_longer_than_any() {
+        let (rare, ultra_rare) = (Rarity::Rare.table(), Rarity::UltraRare.table());
+        let targets = [(&rare, 1), (&ultra_rare, 1)];
+        let all = expected_value(Goal::All, &targets).unwrap();
+        let any = expected_value(Goal::Any, &targets).unwrap();
+        let ultra: f64 = calc::expected_value(&ultra_rare, 1).unwrap();
+        assert!(any < 12.94 && ultra < all, "{any} {all}");
+    }
+
+    #[test]
+    fn targets_parse() {
+        let target: Target = "ultra-rare:12".parse().unwrap();
+        assert_eq!(
+            (target.rarity.as_str(), target.treasure_opening),
+            ("ultra-rare", 12)
+        );
+        assert_eq!("rare".parse::<Target>().unwrap().treasure_opening, 1);
+        for invalid in ["rare:0", ":3", "rare:x"] {
+            assert!(invalid.parse::<Target>().is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! The number types the calculations can be done in: `f64` for everyday answers, or exact fractions of big integers for
+//! reference answers that don't lose any precision along the way
+
+use std::fmt::{self, Debug};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Num, Signed, ToPrimitive, Zero};
+use serde::Serialize;
+
+/// Exact fractions of big integers
+pub type Exact = BigRational;
+
+/// How many significant digits exact results are written out to when no precision is given
+pub const DEFAULT_EXACT_DIGITS: usize = 30;
+
+/// A number the calculations can be done in
+pub trait Number: Num + Clone + Debug + PartialOrd + 'static {
+    /// Odds (or any other number) from an odds table. Exact numbers take the shortest decimal that reads back as `x`,
+    /// so odds of 13.1 are exactly 131/10 rather than the binary fraction nearest to it.
+    fn from_f64(x: f64) -> Self;
+
+    fn from_usize(n: usize) -> Self;
+
+    /// The nearest `f64`
+    fn to_f64(&self) -> f64;
+
+    /// The number as it's printed in results, rounded to `digits` significant digits if given
+    fn print(&self, digits: Option<usize>) -> Printed;
+}
+
+/// A number as it's printed in results. Floats stay numbers, while exact numbers are written out as decimals so they
+/// don't lose any digits on the way.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Printed {
+    Float(f64),
+    Exact(String),
+}
+
+impl fmt::Display for Printed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Printed::Float(x) => write!(f, "{x}"),
+            Printed::Exact(x) => f.write_str(x),
+        }
+    }
+}
+
+/// Round `x` to `digits` significant digits
+pub fn round(x: f64, digits: usize) -> f64 {
+    // Scientific notation puts exactly the digits asked for after the leading one
+    format!("{:.*e}", digits.max(1) - 1, x).parse().unwrap()
+}
+
+impl Number for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn from_usize(n: usize) -> Self {
+        n as f64
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn print(&self, digits: Option<usize>) -> Printed {
+        Printed::Float(digits.map_or(*self, |digits| round(*self, digits)))
+    }
+}
+
+impl Number for Exact {
+    fn from_f64(x: f64) -> Self {
+        // Displaying an f64 gives the shortest decimal that reads back as it, and never uses an exponent
+        let decimal = x.to_string();
+        let (whole, fraction) = decimal.split_once('.').unwrap_or((&decimal, ""));
+        let numer: BigInt = format!("{whole}{fraction}").parse().unwrap();
+        Exact::new(numer, BigInt::from(10).pow(fraction.len() as u32))
+    }
+
+    fn from_usize(n: usize) -> Self {
+        Exact::from_integer(BigInt::from(n))
+    }
+
+    fn to_f64(&self) -> f64 {
+        ToPrimitive::to_f64(self).unwrap_or(f64::NAN)
+    }
+
+    fn print(&self, digits: Option<usize>) -> Printed {
+        Printed::Exact(to_decimal(
+            self,
+            digits.unwrap_or(DEFAULT_EXACT_DIGITS).max(1),
+        ))
+    }
+}
+
+/// Write out an exact number as a decimal rounded to `digits` significant digits, without trailing zeros
+fn to_decimal(x: &Exact, digits: usize) -> String {
+    if x.is_zero() {
+        return "0".to_owned();
+    }
+
+    let ten = Exact::from_integer(BigInt::from(10));
+    let magnitude = x.abs();
+    // The power of 10 of the leading digit, starting from a guess that's at most 1 off
+    let mut exponent =
+        magnitude.numer().to_string().len() as i32 - magnitude.denom().to_string().len() as i32;
+    while ten.pow(exponent) > magnitude {
+        exponent -= 1;
+    }
+    while ten.pow(exponent + 1) <= magnitude {
+        exponent += 1;
+    }
+
+    let scaled = |exponent: i32| {
+        (&magnitude * ten.pow(digits as i32 - 1 - exponent))
+            .round()
+            .to_integer()
+            .to_string()
+    };
+    let mut significant = scaled(exponent);
+    // Rounding up can carry into another digit, like 9.99 to 10.0
+    if significant.len() > digits {
+        exponent += 1;
+        significant = scaled(exponent);
+    }
+
+    // The number of digits before the decimal point
+    let point = exponent + 1;
+    let mut decimal = if point <= 0 {
+        format!("0.{}{significant}", "0".repeat(-point as usize))
+    } else if point as usize >= digits {
+        format!("{significant}{}", "0".repeat(point as usize - digits))
+    } else {
+        let (whole, fraction) = significant.split_at(point as usize);
+        format!("{whole}.{fraction}")
+    };
+    if decimal.contains('.') {
+        decimal.truncate(decimal.trim_end_matches('0').trim_end_matches('.').len());
+    }
+
+    if x.is_negative() {
+        decimal.insert(0, '-');
+    }
+    decimal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact(numer: i64, denom: i64) -> Exact {
+        Exact::new(BigInt::from(numer), BigInt::from(denom))
+    }
+
+    #[test]
+    fn floats_round_to_significant_digits() {
+        assert_eq!(round(0.123456, 3), 0.123);
+        assert_eq!(round(123456., 2), 120000.);
+        assert_eq!(round(9.99, 2), 10.);
+        assert_eq!(round(-0.0012345, 2), -0.0012);
+        assert_eq!(round(2.7e-300, 1), 3e-300);
+        assert_eq!(round(0.66, 0), 0.7);
+        assert_eq!(0.123456.print(Some(2)), Printed::Float(0.12));
+        assert_eq!(0.123456.print(None), Printed::Float(0.123456));
+    }
+
+    #[test]
+    fn decimals_are_rounded_to_significant_digits() {
+        assert_eq!(to_decimal(&exact(1, 3), 5), "0.33333");
+        assert_eq!(to_decimal(&exact(2, 3), 3), "0.667");
+        assert_eq!(to_decimal(&exact(123456, 1), 2), "120000");
+        assert_eq!(to_decimal(&exact(123456, 1000), 4), "123.5");
+        // No trailing zeros, even when the digits run out early
+        assert_eq!(to_decimal(&exact(1, 4), 10), "0.25");
+        assert_eq!(to_decimal(&exact(100, 1), 10), "100");
+        assert_eq!(to_decimal(&exact(0, 1), 3), "0");
+    }
+
+    #[test]
+    fn rounding_up_carries_into_another_digit() {
+        assert_eq!(to_decimal(&exact(999, 100), 2), "10");
+        assert_eq!(to_decimal(&exact(9999, 1000), 3), "10");
+        assert_eq!(to_decimal(&exact(999, 10000), 2), "0.1");
+        assert_eq!(to_decimal(&exact(99999, 1), 3), "100000");
+        assert_eq!(to_decimal(&exact(995, 100), 2), "10");
+        assert_eq!(to_decimal(&exact(994, 100), 2), "9.9");
+    }
+
+    #[test]
+    fn tiny_and_huge_decimals_are_written_out_in_full() {
+        let tiny = exact(1, 3) / Exact::from_integer(BigInt::from(10).pow(20));
+        assert_eq!(to_decimal(&tiny, 3), "0.00000000000000000000333");
+        let huge = Exact::from_integer(BigInt::from(10).pow(40) * 7);
+        assert_eq!(to_decimal(&huge, 2), format!("7{}", "0".repeat(40)));
+        assert_eq!(
+            to_decimal(&(huge.recip() * exact(-1, 1)), 1),
+            format!("-0.{}1", "0".repeat(40))
+        );
+    }
+
+    #[test]
+    fn negative_decimals_round_away_from_zero() {
+        assert_eq!(to_decimal(&exact(-2, 3), 3), "-0.667");
+        assert_eq!(to_decimal(&exact(-999, 100), 2), "-10");
+        assert_eq!(to_decimal(&exact(-1, 8), 2), "-0.13");
+        assert_eq!(
+            exact(-1, 3).print(Some(2)),
+            Printed::Exact("-0.33".to_owned())
+        );
+    }
+
+    #[test]
+    fn exact_numbers_default_to_30_digits() {
+        let third = format!("0.{}", "3".repeat(DEFAULT_EXACT_DIGITS));
+        assert_eq!(exact(1, 3).print(None), Printed::Exact(third));
+        // Asking for no digits still gives one
+        assert_eq!(exact(2, 3).print(Some(0)), Printed::Exact("0.7".to_owned()));
+    }
+
+    #[test]
+    fn floats_are_read_as_the_decimal_they_are_written_as() {
+        assert_eq!(Exact::from_f64(13.1), exact(131, 10));
+        assert_eq!(Exact::from_f64(0.1), exact(1, 10));
+        assert_eq!(Exact::from_f64(-2.5), exact(-5, 2));
+        assert_eq!(Exact::from_f64(250.), exact(250, 1));
+        assert_eq!(Exact::from_f64(1e-7), exact(1, 10_000_000));
+        assert_eq!(
+            Exact::from_f64(1e20),
+            Exact::from_integer(BigInt::from(10).pow(20))
+        );
+        for x in [0.1, 13.1, 1. / 3., -2.5e-10, 6.02e23] {
+            assert_eq!(Number::to_f64(&Exact::from_f64(x)), x);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rarity {
+    Rare,
+    VeryRare,
+    UltraRare,
+}
+
+pub const MAX_ODDS: usize = 50;
+
+impl Rarity {
+    pub const ALL: [Rarity; 3] = [Rarity::Rare, Rarity::VeryRare, Rarity::UltraRare];
+
+    pub fn odds(&self) -> &'static [f64; MAX_ODDS] {
+        match self {
+            Rarity::Rare => &[
+                20_000., 583., 187., 88., 51., 33., 23., 17., 13.1, 10.4, 8.5, 7.1, 6.0, 5.2, 4.5,
+                4.0, 3.6, 3.2, 2.9, 2.6, 2.4, 2.2, 2.1, 1.9, 1.8, 1.7, 1.6, 1.5, 1.5, 1.4, 1.3,
+                1.3, 1.2, 1.2, 1.2, 1.1, 1.1, 1.1, 1.1, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+                1.0, 1.0, 1.0,
+            ],
+            Rarity::VeryRare => &[
+                20_000., 3_653., 1_059., 485., 276., 178., 124., 92., 70., 56., 45., 38., 32., 27.,
+                24., 21., 18., 16., 14.1, 12.7, 11.5, 10.5, 9.6, 8.8, 8.1, 7.5, 7.0, 6.5, 6.0, 5.7,
+                5.3, 5.0, 4.7, 4.5, 4.2, 4.0, 3.8, 3.6, 3.4, 3.3, 3.2, 3.0, 2.9, 2.8, 2.7, 2.6,
+                2.5, 2.4, 2.3, 2.2,
+            ],
+            Rarity::UltraRare => &[
+                100_000., 27_380., 8_614., 4_021., 2_303., 1_486., 1_037., 764., 586., 464., 376.,
+                311., 262., 223., 193., 168., 148., 131., 117., 105., 95., 86., 79., 72., 66., 61.,
+                57., 53., 49., 46., 43., 40., 38., 35., 33., 32., 30., 28., 27., 26., 24., 23.,
+                22., 21., 20., 19., 19., 18., 17., 17.,
+            ],
+        }
+    }
+
+    /// The name this rarity goes by on the command line and in odds files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rarity::Rare => "rare",
+            Rarity::VeryRare => "very-rare",
+            Rarity::UltraRare => "ultra-rare",
+        }
+    }
+
+    /// Look up a rarity by the name it goes by on the command line and in odds files
+    pub fn from_name(name: &str) -> Option<Rarity> {
+        Rarity::ALL.into_iter().find(|rarity| rarity.name() == name)
+    }
+
+    /// The built-in odds table for this rarity
+    pub fn table(&self) -> OddsTable {
+        OddsTable {
+            name: self.name().to_owned(),
+            odds: self.odds().to_vec(),
+            tail: Tail::RepeatLast,
+        }
+    }
+}
+
+/// What happens to the odds once you've opened more treasures than a table lists
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(untagged)]
+pub enum Tail {
+    /// Keep using the last listed odds forever. Written as `"repeat-last"`.
+    #[default]
+    #[serde(with = "repeat_last")]
+    RepeatLast,
+    /// Use a fixed "1 in N" chance for every later opening. Written as a number.
+    Fixed(f64),
+}
+
+mod repeat_last {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str("repeat-last")
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<(), D::Error> {
+        match String::deserialize(d)?.as_str() {
+            "repeat-last" => Ok(()),
+            other => Err(D::Error::custom(format!(
+                "unknown tail behavior `{other}`, expected \"repeat-last\" or a number"
+            ))),
+        }
+    }
+}
+
+/// The escalating odds of opening an item of one rarity
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct OddsTable {
+    /// The name used to pick this table on the command line
+    pub name: String,
+    /// The "1 in N" odds of each treasure opening, starting with the first
+    pub odds: Vec<f64>,
+    /// The behavior after the last listed opening
+    #[serde(default)]
+    pub tail: Tail,
+}
+
+impl OddsTable {
+    /// The "1 in N" odds used for every opening past the end of the table
+    pub fn tail_odds(&self) -> f64 {
+        match self.tail {
+            Tail::RepeatLast => *self.odds.last().unwrap(),
+            Tail::Fixed(odds) => odds,
+        }
+    }
+
+    /// The "1 in N" odds of each opening, starting with `treasure_opening` and continuing forever
+    pub fn odds_from(&self, treasure_opening: usize) -> impl Iterator<Item = f64> + '_ {
+        self.odds
+            .iter()
+            .copied()
+            .chain(std::iter::repeat(self.tail_odds()))
+            .skip(treasure_opening - 1)
+    }
+
+    /// Make sure the table can be calculated with: it needs at least one opening, and every opening needs odds of
+    /// "1 in N" for some finite N of at least 1
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| {
+            Err(Error::InvalidOddsTable {
+                table: self.name.clone(),
+                reason,
+            })
+        };
+
+        if self.odds.is_empty() {
+            return invalid("needs at least one opening".to_owned());
+        }
+        if let Some((i, odds)) = self
+            .odds
+            .iter()
+            .enumerate()
+            .find(|(_, odds)| !(**odds >= 1. && odds.is_finite()))
+        {
+            return invalid(format!(
+                "opening {} has odds of 1 in {odds}, odds must be 1 in N for some finite N >= 1",
+                i + 1
+            ));
+        }
+        if let Tail::Fixed(odds) = self.tail {
+            if !(odds >= 1. && odds.is_finite()) {
+                return invalid(format!(
+                    "tail has odds of 1 in {odds}, odds must be 1 in N for some finite N >= 1"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Make sure the table can be calculated with, starting at `treasure_opening`
+    pub(crate) fn check(&self, treasure_opening: usize) -> Result<(), Error> {
+        if treasure_opening < 1 {
+            return Err(Error::InvalidTreasureOpening(treasure_opening));
+        }
+        self.validate()
+    }
+}
+
+/// A collection of odds tables that can be looked up by name
+#[derive(Clone, Debug, Default)]
+pub struct OddsTables {
+    tables: Vec<OddsTable>,
+}
+
+impl OddsTables {
+    /// The tables for the built-in rarities
+    pub fn builtin() -> Self {
+        OddsTables {
+            tables: Rarity::ALL.iter().map(Rarity::table).collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OddsTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OddsTable> {
+        self.tables.iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tables.iter().map(|table| table.name.as_str())
+    }
+
+    /// Add tables to the collection, replacing any existing tables with the same name
+    pub fn extend(&mut self, tables: impl IntoIterator<Item = OddsTable>) {
+        for table in tables {
+            match self.tables.iter_mut().find(|t| t.name == table.name) {
+                Some(existing) => *existing = table,
+                None => self.tables.push(table),
+            }
+        }
+    }
+}
+
+/// The contents of an odds file. See README.md for the format.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OddsFile {
+    /// The treasure these tables belong to. Tables without a treasure are added to the built-in one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treasure: Option<String>,
+    /// A short description of the treasure, shown by `list-treasures`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "table")]
+    pub tables: Vec<OddsTable>,
+}
+
+impl OddsFile {
+    /// Load an odds file. Files ending in `.json` are read as JSON, anything else as TOML.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        let file: OddsFile = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(|e| Error::ParseOddsFile(e.to_string()))?
+        } else {
+            toml::from_str(&contents).map_err(|e| Error::ParseOddsFile(e.to_string()))?
+        };
+
+        for table in &file.tables {
+            table.validate()?;
+        }
+
+        Ok(file)
+    }
+
+    /// Save an odds file that `load` can read back, as JSON if the path ends in `.json` and as TOML otherwise
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let failed =
+            |e: &dyn std::fmt::Display| Error::WriteOddsFile(format!("{}: {e}", path.display()));
+        let contents = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_string_pretty(self).map_err(|e| failed(&e))? + "\n"
+        } else {
+            toml::to_string(self).map_err(|e| failed(&e))?
+        };
+        fs::write(path, contents).map_err(|e| failed(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(odds: &[f64], tail: Tail) -> OddsTable {
+        OddsTable {
+            name: "arcana".to_owned(),
+            odds: odds.to_vec(),
+            tail,
+        }
+    }
+
+    /// A fresh directory to write odds files to
+    fn dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("dota-odds-calc-odds-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn tails_are_repeat_last_or_a_number() {
+        let tail = |json: &str| serde_json::from_str::<Tail>(json);
+        assert_eq!(tail(r#""repeat-last""#).unwrap(), Tail::RepeatLast);
+        assert_eq!(tail("25").unwrap(), Tail::Fixed(25.));
+        assert_eq!(tail("2.5").unwrap(), Tail::Fixed(2.5));
+        assert!(tail(r#""forever""#).is_err());
+
+        assert_eq!(
+            serde_json::to_string(&Tail::RepeatLast).unwrap(),
+            r#""repeat-last""#
+        );
+        assert_eq!(serde_json::to_string(&Tail::Fixed(25.)).unwrap(), "25.0");
+        // Leaving the tail out repeats the last odds
+        let table: OddsTable = toml::from_str("name = \"arcana\"\nodds = [4, 2]\n").unwrap();
+        assert_eq!(table.tail, Tail::RepeatLast);
+    }
+
+    #[test]
+    fn odds_continue_with_the_tail() {
+        let repeat: Vec<f64> = table(&[4., 2.], Tail::RepeatLast)
+            .odds_from(2)
+            .take(3)
+            .collect();
+        assert_eq!(repeat, [2., 2., 2.]);
+        let fixed: Vec<f64> = table(&[4., 2.], Tail::Fixed(3.))
+            .odds_from(1)
+            .take(4)
+            .collect();
+        assert_eq!(fixed, [4., 2., 3., 3.]);
+    }
+
+    #[test]
+    fn invalid_tables_are_rejected() {
+        let invalid =
+            |table: OddsTable| matches!(table.validate(), Err(Error::InvalidOddsTable { .. }));
+        assert!(table(&[4., 1.], Tail::RepeatLast).validate().is_ok());
+        assert!(invalid(table(&[], Tail::RepeatLast)));
+        assert!(invalid(table(&[4., 0.5], Tail::RepeatLast)));
+        assert!(invalid(table(&[f64::INFINITY], Tail::RepeatLast)));
+        assert!(invalid(table(&[f64::NAN], Tail::RepeatLast)));
+        assert!(invalid(table(&[4.], Tail::Fixed(0.))));
+        assert!(matches!(
+            table(&[4.], Tail::RepeatLast).check(0),
+            Err(Error::InvalidTreasureOpening(0))
+        ));
+    }
+
+    #[test]
+    fn odds_files_load_from_toml_and_json() {
+        let dir = dir("load");
+        let toml = dir.join("odds.toml");
+        fs::write(
+            &toml,
+            "treasure = \"cache\"\ndescription = \"A cache\"\n\n[[table]]\nname = \"arcana\"\nodds = [4, 2]\ntail = 3\n",
+        )
+        .unwrap();
+        let file = OddsFile::load(&toml).unwrap();
+        assert_eq!(file.treasure.as_deref(), Some("cache"));
+        assert_eq!(file.description.as_deref(), Some("A cache"));
+        assert_eq!(file.tables, [table(&[4., 2.], Tail::Fixed(3.))]);
+
+        let json = dir.join("odds.json");
+        fs::write(
+            &json,
+            r#"{"table": [{"name": "arcana", "odds": [4, 2], "tail": "repeat-last"}]}"#,
+        )
+        .unwrap();
+        let file = OddsFile::load(&json).unwrap();
+        assert_eq!(file.treasure, None);
+        assert_eq!(file.tables[0].tail, Tail::RepeatLast);
+
+        // Saved files read back the same
+        for path in [dir.join("saved.toml"), dir.join("saved.json")] {
+            file.save(&path).unwrap();
+            assert_eq!(OddsFile::load(&path).unwrap().tables, file.tables);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bad_odds_files_are_errors() {
+        let dir = dir("bad");
+        let path = dir.join("odds.toml");
+        fs::write(&path, "[[table]]\nname = \"arcana\"\nodds = []\n").unwrap();
+        assert!(matches!(
+            OddsFile::load(&path),
+            Err(Error::InvalidOddsTable { .. })
+        ));
+        fs::write(
+            &path,
+            "[[table]]\nname = \"arcana\"\nodds = [4]\ntail = \"forever\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            OddsFile::load(&path),
+            Err(Error::ParseOddsFile(_))
+        ));
+        assert!(matches!(
+            OddsFile::load(&dir.join("missing.toml")),
+            Err(Error::Io(_))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,1245 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use csv::Writer;
+use serde::Serialize;
+
+use dota_odds_calc::{
+    calc::{DistributionPoint, Summary},
+    chart::{AxisValue, Chart, Measure, Quantity},
+    cost::Pricing,
+    estimate::Fit,
+    expected_value,
+    goodness_of_fit::GoodnessOfFit,
+    joint::{JointResult, Overlap},
+    ledger::Ledger,
+    multi::{Goal, Target},
+    number::{self, Number, Printed},
+    plot::{cell_plot, expected_value_plot, heatmap, LinePlot},
+    renewal::DropsPoint,
+    sim::Estimate,
+    treasure::Treasures,
+    OddsTable,
+};
+
+/// How results are printed
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text for reading in the terminal
+    Text,
+    /// JSON records that include the inputs each result was calculated from
+    Json,
+    /// CSV records that include the inputs each result was calculated from
+    Csv,
+}
+
+/// Write `records` as a JSON array or as CSV rows
+fn write_records<T: Serialize>(
+    mut out: impl Write,
+    format: Format,
+    records: &[T],
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, records)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            let mut wtr = Writer::from_writer(out);
+            for record in records {
+                wtr.serialize(record)?;
+            }
+            wtr.flush()?;
+        }
+        Format::Text => unreachable!("text output is written by hand"),
+    }
+    Ok(())
+}
+
+/// Write a single record as a JSON object or as a CSV row
+fn write_record<T: Serialize>(
+    mut out: impl Write,
+    format: Format,
+    record: &T,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, record)?;
+            writeln!(out)?;
+            Ok(())
+        }
+        _ => write_records(out, format, std::slice::from_ref(record)),
+    }
+}
+
+fn print_records<T: Serialize>(format: Format, records: &[T]) -> Result<(), Box<dyn Error>> {
+    write_records(io::stdout().lock(), format, records)
+}
+
+fn print_record<T: Serialize>(format: Format, record: &T) -> Result<(), Box<dyn Error>> {
+    write_record(io::stdout().lock(), format, record)
+}
+
+/// Round `x` to `precision` significant digits, if given
+fn rounded(x: f64, precision: Option<usize>) -> f64 {
+    precision.map_or(x, |digits| number::round(x, digits))
+}
+
+/// Write a probability for a column of a text table: rounded to `precision` significant digits if given, or else to 6
+/// decimal places (or the digits exact numbers are written out to)
+fn cell<N: Number>(x: &N, precision: Option<usize>) -> String {
+    match x.print(precision) {
+        Printed::Float(x) => fixed(x, 6, precision),
+        printed => printed.to_string(),
+    }
+}
+
+/// Write `x` for a column of a text table: rounded to `precision` significant digits if given, or else to `decimals`
+/// decimal places. Rounded numbers too small to write out in full, like tiny p-values, get an exponent.
+fn fixed(x: f64, decimals: usize, precision: Option<usize>) -> String {
+    match precision.map(|digits| number::round(x, digits)) {
+        Some(x) if x != 0. && x.abs() < 1e-6 => format!("{x:e}"),
+        Some(x) => x.to_string(),
+        None => format!("{x:.decimals$}"),
+    }
+}
+
+/// A copy of `test` with its statistics and the deviation of each opening rounded to `precision` significant digits
+fn rounded_goodness_of_fit(test: &GoodnessOfFit, precision: Option<usize>) -> GoodnessOfFit {
+    let round = |x| rounded(x, precision);
+    let mut test = test.clone();
+    for x in [
+        &mut test.g,
+        &mut test.g_p_value,
+        &mut test.chi_square,
+        &mut test.chi_square_p_value,
+    ] {
+        *x = round(*x);
+    }
+    for opening in &mut test.openings {
+        for x in [
+            &mut opening.published,
+            &mut opening.observed,
+            &mut opening.expected_drops,
+            &mut opening.z,
+            &mut opening.g,
+        ] {
+            *x = round(*x);
+        }
+    }
+    test
+}
+
+/// A copy of `chart` with its expected values and probabilities rounded to `precision` significant digits
+fn rounded_chart(chart: &Chart, precision: Option<usize>) -> Chart {
+    let mut chart = chart.clone();
+    for line in chart.rows.iter_mut().chain(&mut chart.columns) {
+        line.expected_value = line.expected_value.map(|ev| rounded(ev, precision));
+    }
+    for cell in chart.cells.iter_mut().flatten() {
+        *cell = rounded(*cell, precision);
+    }
+    chart
+}
+
+pub fn goal_name(goal: Goal) -> &'static str {
+    match goal {
+        Goal::All => "all",
+        Goal::Any => "any",
+    }
+}
+
+pub fn overlap_name(overlap: Overlap) -> &'static str {
+    match overlap {
+        Overlap::RarestFirst => "rarest-first",
+        Overlap::Normalize => "normalize",
+    }
+}
+
+/// The image formats charts can be plotted in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotFormat {
+    Svg,
+    /// Only available when built with the png feature
+    Png,
+    /// Print a heatmap and a line plot straight to the terminal instead of saving images
+    Terminal,
+}
+
+impl PlotFormat {
+    /// Render a plot to the bytes of an image file
+    fn render(self, plot: &LinePlot) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            PlotFormat::Svg => Ok(plot.to_svg().into_bytes()),
+            #[cfg(feature = "png")]
+            PlotFormat::Png => Ok(plot.to_png()?),
+            #[cfg(not(feature = "png"))]
+            PlotFormat::Png => {
+                Err("PNG plots need the calculator to be built with --features png".into())
+            }
+            PlotFormat::Terminal => unreachable!("terminal plots are printed, not saved"),
+        }
+    }
+}
+
+/// The height of line plots drawn in the terminal, not counting their axes and legend
+const TERMINAL_PLOT_HEIGHT: usize = 16;
+
+/// Plot a chart's cells next to the chart file, as `<chart>-probability.<ext>` or `<chart>-expected-value.<ext>`
+/// depending on what the cells hold. Charts of probabilities by treasure opening also get their expected values
+/// plotted, as `<chart>-expected-value.<ext>`. `suffix` is added to the file names to tell several charts apart. Terminal plots
+/// are printed instead, fitted to the width of the terminal.
+pub fn write_plots(
+    chart: &Chart,
+    title: &str,
+    out: &Path,
+    suffix: &str,
+    formats: &[PlotFormat],
+) -> Result<(), Box<dyn Error>> {
+    let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+    let cells = match chart.measure {
+        Measure::Probability => "probability",
+        Measure::ExpectedValue => "expected-value",
+    };
+    let plots: Vec<_> = [
+        (cells, Some(cell_plot(chart, title))),
+        ("expected-value", expected_value_plot(chart, title)),
+    ]
+    .into_iter()
+    .filter_map(|(name, plot)| Some((name, plot?)))
+    .collect();
+    for &format in formats {
+        let extension = match format {
+            PlotFormat::Svg => "svg",
+            PlotFormat::Png => "png",
+            PlotFormat::Terminal => {
+                print_terminal_plots(chart, title);
+                continue;
+            }
+        };
+        for (name, plot) in &plots {
+            let path = out.with_file_name(format!("{stem}{suffix}-{name}.{extension}"));
+            std::fs::write(path, format.render(plot)?)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn print_terminal_plots(chart: &Chart, title: &str) {
+    let stdout = io::stdout();
+    // Colors and the terminal's size only make sense when printing to a terminal, not a pipe or a file
+    let (width, color) = if stdout.is_terminal() {
+        let width = crossterm::terminal::size().map_or(80, |(columns, _)| columns as usize);
+        (width, true)
+    } else {
+        (80, false)
+    };
+
+    println!("{}", heatmap(chart, title, width, color));
+    print!(
+        "{}",
+        cell_plot(chart, title).to_text(width, TERMINAL_PLOT_HEIGHT)
+    );
+}
+
+/// Prints the results of the modes that calculate with a single rarity, along with what they were calculated from
+pub struct Output<'a> {
+    pub format: Format,
+    pub treasure: &'a str,
+    pub rarity: &'a str,
+    pub treasure_opening: usize,
+    pub pricing: Option<&'a Pricing>,
+    /// The number of significant digits to round calculated probabilities and expected values to
+    pub precision: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ExpectedValueRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    expected_value: Printed,
+    variance: Option<f64>,
+    std_dev: Option<f64>,
+    median: Option<usize>,
+    percentile_10: Option<usize>,
+    percentile_90: Option<usize>,
+    expected_spend: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ProbabilityRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    boxes: usize,
+    budget: Option<f64>,
+    cost: Option<f64>,
+    probability: Printed,
+}
+
+#[derive(Serialize)]
+struct DistributionRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    boxes: usize,
+    opening: usize,
+    exactly: Printed,
+    by: Printed,
+}
+
+#[derive(Serialize)]
+struct QuantileRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    level: f64,
+    /// Empty if no number of boxes reaches the level
+    boxes: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct DropsRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    boxes: usize,
+    expected_drops: f64,
+    drops: usize,
+    exactly: f64,
+    at_least: f64,
+}
+
+#[derive(Serialize)]
+struct EstimateRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    /// The credible level of the interval
+    level: f64,
+    monotone: bool,
+    treasure_opening: usize,
+    boxes: u64,
+    drops: u64,
+    fitted: f64,
+    ci_low: f64,
+    ci_high: f64,
+    /// The drop probability of the published odds
+    published: f64,
+    /// Whether the published probability lies outside the credible interval
+    outside: bool,
+}
+
+#[derive(Serialize)]
+struct SimulationRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    trials: usize,
+    seed: u64,
+    /// Either `expected_value` or `probability`
+    quantity: &'static str,
+    /// The number of boxes a probability is for
+    boxes: Option<usize>,
+    simulated: f64,
+    ci_low: f64,
+    ci_high: f64,
+    analytic: f64,
+    disagrees: bool,
+}
+
+#[derive(Serialize)]
+struct ChartRecord<'a> {
+    treasure: &'a str,
+    rarity: Option<&'a str>,
+    treasure_opening: Option<usize>,
+    expected_value: Option<f64>,
+    expected_spend: Option<f64>,
+    boxes: Option<usize>,
+    budget: Option<f64>,
+    cost: Option<f64>,
+    probability: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ChartJson<'a> {
+    treasure: &'a str,
+    #[serde(flatten)]
+    chart: &'a Chart,
+}
+
+#[derive(Serialize)]
+struct GoodnessOfFitJson<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    #[serde(flatten)]
+    test: &'a GoodnessOfFit,
+}
+
+#[derive(Serialize)]
+struct GoodnessOfFitRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    g: f64,
+    g_p_value: f64,
+    chi_square: f64,
+    chi_square_p_value: f64,
+    degrees_of_freedom: usize,
+    // The fields of the opening, since CSV can't flatten a nested struct
+    treasure_opening: usize,
+    boxes: u64,
+    drops: u64,
+    published: f64,
+    observed: f64,
+    expected_drops: f64,
+    z: f64,
+    opening_g: f64,
+}
+
+impl Output<'_> {
+    pub fn expected_value<N: Number>(
+        &self,
+        expected_value: N,
+        summary: Option<&Summary>,
+        expected_spend: Option<f64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let expected_value = expected_value.print(self.precision);
+        let round = |x| rounded(x, self.precision);
+        if self.format == Format::Text {
+            match summary {
+                None => println!("{}", expected_value),
+                Some(summary) => {
+                    println!("expected value:     {}", expected_value);
+                    println!("variance:           {}", round(summary.variance));
+                    println!("standard deviation: {}", round(summary.std_dev));
+                    println!("median:             {}", summary.median);
+                    println!("10th percentile:    {}", summary.percentile_10);
+                    println!("90th percentile:    {}", summary.percentile_90);
+                }
+            }
+            if let (Some(pricing), Some(spend)) = (self.pricing, expected_spend) {
+                let label = if summary.is_some() {
+                    "expected spend:    "
+                } else {
+                    "expected spend:"
+                };
+                println!("{label} {}", pricing.display(spend));
+            }
+            return Ok(());
+        }
+
+        print_record(
+            self.format,
+            &ExpectedValueRecord {
+                treasure: self.treasure,
+                rarity: self.rarity,
+                treasure_opening: self.treasure_opening,
+                expected_value,
+                variance: summary.map(|s| round(s.variance)),
+                std_dev: summary.map(|s| round(s.std_dev)),
+                median: summary.map(|s| s.median),
+                percentile_10: summary.map(|s| s.percentile_10),
+                percentile_90: summary.map(|s| s.percentile_90),
+                expected_spend,
+            },
+        )
+    }
+
+    pub fn probability<N: Number>(
+        &self,
+        boxes: usize,
+        budget: Option<f64>,
+        probability: N,
+    ) -> Result<(), Box<dyn Error>> {
+        let probability = probability.print(self.precision);
+        let cost = self.pricing.map(|pricing| pricing.cost(boxes));
+        if self.format == Format::Text {
+            println!("{}", probability);
+            if let (Some(pricing), Some(cost)) = (self.pricing, cost) {
+                println!("{boxes} boxes for {}", pricing.display(cost));
+            }
+            return Ok(());
+        }
+
+        print_record(
+            self.format,
+            &ProbabilityRecord {
+                treasure: self.treasure,
+                rarity: self.rarity,
+                treasure_opening: self.treasure_opening,
+                boxes,
+                budget,
+                cost,
+                probability,
+            },
+        )
+    }
+
+    pub fn distribution<N: Number>(
+        &self,
+        points: &[DistributionPoint<N>],
+    ) -> Result<(), Box<dyn Error>> {
+        if self.format == Format::Text {
+            println!(
+                "{:>6} {:>8} {:>12} {:>12}",
+                "boxes", "opening", "P(exactly)", "P(by)"
+            );
+            for point in points {
+                println!(
+                    "{:>6} {:>8} {:>12} {:>12}",
+                    point.boxes,
+                    point.opening,
+                    cell(&point.exactly, self.precision),
+                    cell(&point.by, self.precision)
+                );
+            }
+            return Ok(());
+        }
+
+        let records: Vec<_> = points
+            .iter()
+            .map(|point| DistributionRecord {
+                treasure: self.treasure,
+                rarity: self.rarity,
+                treasure_opening: self.treasure_opening,
+                boxes: point.boxes,
+                opening: point.opening,
+                exactly: point.exactly.print(self.precision),
+                by: point.by.print(self.precision),
+            })
+            .collect();
+        print_records(self.format, &records)
+    }
+
+    /// Print the distribution of the number of items got in `num_boxes` boxes
+    pub fn drops(
+        &self,
+        num_boxes: usize,
+        expected_drops: f64,
+        points: &[DropsPoint],
+    ) -> Result<(), Box<dyn Error>> {
+        let expected_drops = rounded(expected_drops, self.precision);
+        if self.format == Format::Text {
+            println!("expected items in {num_boxes} boxes: {expected_drops}");
+            println!();
+            println!("{:>6} {:>12} {:>12}", "items", "P(exactly)", "P(at least)");
+            for point in points {
+                println!(
+                    "{:>6} {:>12} {:>12}",
+                    point.drops,
+                    cell(&point.exactly, self.precision),
+                    cell(&point.at_least, self.precision)
+                );
+            }
+            return Ok(());
+        }
+
+        let records: Vec<_> = points
+            .iter()
+            .map(|point| DropsRecord {
+                treasure: self.treasure,
+                rarity: self.rarity,
+                treasure_opening: self.treasure_opening,
+                boxes: num_boxes,
+                expected_drops,
+                drops: point.drops,
+                exactly: rounded(point.exactly, self.precision),
+                at_least: rounded(point.at_least, self.precision),
+            })
+            .collect();
+        print_records(self.format, &records)
+    }
+
+    pub fn quantiles(&self, quantiles: &[(f64, Option<usize>)]) -> Result<(), Box<dyn Error>> {
+        if self.format == Format::Text {
+            for (level, boxes) in quantiles {
+                match boxes {
+                    Some(boxes) => println!("{level}: {boxes}"),
+                    None => println!("{level}: never"),
+                }
+            }
+            return Ok(());
+        }
+
+        let records: Vec<_> = quantiles
+            .iter()
+            .map(|&(level, boxes)| QuantileRecord {
+                treasure: self.treasure,
+                rarity: self.rarity,
+                treasure_opening: self.treasure_opening,
+                level,
+                boxes,
+            })
+            .collect();
+        print_records(self.format, &records)
+    }
+
+    /// Print the drop probability fitted to each opening of a log, next to the published odds
+    pub fn estimate(
+        &self,
+        level: f64,
+        monotone: bool,
+        fits: &[Fit],
+        published: &OddsTable,
+    ) -> Result<(), Box<dyn Error>> {
+        let round = |x| rounded(x, self.precision);
+        let records: Vec<_> = fits
+            .iter()
+            .zip(published.odds_from(1))
+            .map(|(fit, odds)| {
+                let published = 1. / odds;
+                EstimateRecord {
+                    treasure: self.treasure,
+                    rarity: self.rarity,
+                    level,
+                    monotone,
+                    treasure_opening: fit.treasure_opening,
+                    boxes: fit.boxes,
+                    drops: fit.drops,
+                    fitted: round(fit.probability),
+                    ci_low: round(fit.lower),
+                    ci_high: round(fit.upper),
+                    published: round(published),
+                    outside: !(fit.lower..=fit.upper).contains(&published),
+                }
+            })
+            .collect();
+
+        if self.format != Format::Text {
+            return print_records(self.format, &records);
+        }
+
+        let interval = format!("{}% CI", level * 100.);
+        println!(
+            "{:>7} {:>6} {:>6} {:>10} {:>23} {:>10}",
+            "opening", "boxes", "drops", "fitted", interval, "published"
+        );
+        let fixed = |x| fixed(x, 6, self.precision);
+        for record in &records {
+            println!(
+                "{:>7} {:>6} {:>6} {:>10} {:>10} to {:>10} {:>10}{}",
+                record.treasure_opening,
+                record.boxes,
+                record.drops,
+                fixed(record.fitted),
+                fixed(record.ci_low),
+                fixed(record.ci_high),
+                fixed(record.published),
+                if record.outside { "  <- outside" } else { "" }
+            );
+        }
+
+        println!();
+        let outside = records.iter().filter(|record| record.outside).count();
+        println!(
+            "The published odds of {outside} of {} openings lie outside the fitted {interval}",
+            records.len()
+        );
+        Ok(())
+    }
+
+    /// Print the result of testing a log against the published odds, with the `top` openings that deviate most
+    pub fn goodness_of_fit(&self, test: &GoodnessOfFit, top: usize) -> Result<(), Box<dyn Error>> {
+        let test = &rounded_goodness_of_fit(test, self.precision);
+        match self.format {
+            Format::Json => {
+                return print_record(
+                    self.format,
+                    &GoodnessOfFitJson {
+                        treasure: self.treasure,
+                        rarity: self.rarity,
+                        test,
+                    },
+                )
+            }
+            Format::Csv => {
+                let records: Vec<_> = test
+                    .openings
+                    .iter()
+                    .map(|opening| GoodnessOfFitRecord {
+                        treasure: self.treasure,
+                        rarity: self.rarity,
+                        g: test.g,
+                        g_p_value: test.g_p_value,
+                        chi_square: test.chi_square,
+                        chi_square_p_value: test.chi_square_p_value,
+                        degrees_of_freedom: test.degrees_of_freedom,
+                        treasure_opening: opening.treasure_opening,
+                        boxes: opening.boxes,
+                        drops: opening.drops,
+                        published: opening.published,
+                        observed: opening.observed,
+                        expected_drops: opening.expected_drops,
+                        z: opening.z,
+                        opening_g: opening.g,
+                    })
+                    .collect();
+                return print_records(self.format, &records);
+            }
+            Format::Text => {}
+        }
+
+        let boxes: u64 = test.openings.iter().map(|opening| opening.boxes).sum();
+        println!("{boxes} boxes over {} openings", test.degrees_of_freedom);
+        let fixed = |x, decimals| fixed(x, decimals, self.precision);
+        println!(
+            "likelihood ratio: G = {}, p = {}",
+            fixed(test.g, 4),
+            fixed(test.g_p_value, 6)
+        );
+        println!(
+            "Pearson's chi-square: X² = {}, p = {}",
+            fixed(test.chi_square, 4),
+            fixed(test.chi_square_p_value, 6)
+        );
+        println!("(both with {} degrees of freedom)", test.degrees_of_freedom);
+
+        println!();
+        println!("openings that deviate most from the published odds:");
+        println!(
+            "{:>7} {:>6} {:>6} {:>10} {:>10} {:>10} {:>8}",
+            "opening", "boxes", "drops", "expected", "observed", "published", "z"
+        );
+        for opening in test.openings.iter().take(top) {
+            println!(
+                "{:>7} {:>6} {:>6} {:>10} {:>10} {:>10} {:>8}",
+                opening.treasure_opening,
+                opening.boxes,
+                opening.drops,
+                fixed(opening.expected_drops, 3),
+                fixed(opening.observed, 6),
+                fixed(opening.published, 6),
+                fixed(opening.z, 2)
+            );
+        }
+        Ok(())
+    }
+
+    /// Print a simulation's mean number of boxes and the fraction of trials that got the item within each number of
+    /// boxes, next to the analytic results
+    pub fn simulation(
+        &self,
+        trials: usize,
+        seed: u64,
+        mean: &Estimate,
+        expected_value: f64,
+        fractions: &[Estimate],
+        probabilities: &[f64],
+    ) -> Result<(), Box<dyn Error>> {
+        let round = |x| rounded(x, self.precision);
+        let records: Vec<_> = std::iter::once((None, mean, expected_value))
+            .chain(
+                fractions
+                    .iter()
+                    .zip(probabilities)
+                    .enumerate()
+                    .map(|(i, (fraction, &prob))| (Some(i + 1), fraction, prob)),
+            )
+            .map(|(boxes, estimate, analytic)| {
+                let (low, high) = estimate.confidence_interval();
+                let (low, high) = match boxes {
+                    Some(_) => (low.max(0.), high.min(1.)),
+                    None => (low, high),
+                };
+                SimulationRecord {
+                    treasure: self.treasure,
+                    rarity: self.rarity,
+                    treasure_opening: self.treasure_opening,
+                    trials,
+                    seed,
+                    quantity: if boxes.is_some() {
+                        "probability"
+                    } else {
+                        "expected_value"
+                    },
+                    boxes,
+                    simulated: round(estimate.value),
+                    ci_low: round(low),
+                    ci_high: round(high),
+                    analytic: round(analytic),
+                    disagrees: estimate.disagrees_with(analytic),
+                }
+            })
+            .collect();
+
+        if self.format != Format::Text {
+            return print_records(self.format, &records);
+        }
+
+        let flag = |disagrees| if disagrees { "  <- disagrees" } else { "" };
+        let (mean, probabilities) = records.split_first().unwrap();
+        println!("{trials} trials with seed {seed}");
+        let fixed = |x, decimals| fixed(x, decimals, self.precision);
+        println!(
+            "expected value: simulated {} (95% CI {} to {}), analytic {}{}",
+            fixed(mean.simulated, 4),
+            fixed(mean.ci_low, 4),
+            fixed(mean.ci_high, 4),
+            fixed(mean.analytic, 4),
+            flag(mean.disagrees)
+        );
+
+        println!();
+        println!(
+            "{:>6} {:>10} {:>23} {:>10}",
+            "boxes", "simulated", "95% CI", "analytic"
+        );
+        for record in probabilities {
+            println!(
+                "{:>6} {:>10} {:>10} to {:>10} {:>10}{}",
+                record.boxes.unwrap(),
+                fixed(record.simulated, 6),
+                fixed(record.ci_low, 6),
+                fixed(record.ci_high, 6),
+                fixed(record.analytic, 6),
+                flag(record.disagrees)
+            );
+        }
+
+        println!();
+        match records.iter().filter(|record| record.disagrees).count() {
+            0 => println!("The simulation agrees with the analytic results"),
+            n => println!(
+                "{n} result(s) differ from the analytic results by more than {} standard errors",
+                dota_odds_calc::sim::DISAGREEMENT_Z
+            ),
+        }
+        Ok(())
+    }
+
+    /// Write a chart to `out`. As text, the chart is a CSV grid with a row and a column for each value along its axes.
+    pub fn chart(&self, chart: &Chart, out: &Path) -> Result<(), Box<dyn Error>> {
+        let chart = &rounded_chart(chart, self.precision);
+        match self.format {
+            Format::Text => {
+                let mut wtr = Writer::from_path(out)?;
+                write_chart_grid(&mut wtr, chart, "", self.pricing)?;
+                wtr.flush()?;
+                Ok(())
+            }
+            Format::Json => write_record(
+                File::create(out)?,
+                self.format,
+                &ChartJson {
+                    treasure: self.treasure,
+                    chart,
+                },
+            ),
+            Format::Csv => write_records(
+                File::create(out)?,
+                self.format,
+                &chart_records(chart, self.treasure, None),
+            ),
+        }
+    }
+}
+
+/// A record for each cell of a chart. `rarity` is used for charts that have neither a rarity of their own nor rarity on
+/// an axis.
+fn chart_records<'a>(
+    chart: &'a Chart,
+    treasure: &'a str,
+    rarity: Option<&'a str>,
+) -> Vec<ChartRecord<'a>> {
+    let mut records = Vec::new();
+    for (row, cells) in chart.rows.iter().zip(&chart.cells) {
+        for (column, &cell) in chart.columns.iter().zip(cells) {
+            let mut record = ChartRecord {
+                treasure,
+                rarity: chart.rarity.as_deref().or(rarity),
+                treasure_opening: chart.treasure_opening,
+                expected_value: None,
+                expected_spend: None,
+                boxes: None,
+                budget: None,
+                cost: None,
+                probability: None,
+            };
+            for (quantity, line) in [(chart.row_quantity, row), (chart.column_quantity, column)] {
+                match (quantity, &line.value) {
+                    (Quantity::Opening, AxisValue::Count(opening)) => {
+                        record.treasure_opening = Some(*opening)
+                    }
+                    (Quantity::Boxes, AxisValue::Count(boxes)) => record.boxes = Some(*boxes),
+                    (Quantity::Budget, AxisValue::Amount(budget)) => {
+                        record.budget = Some(*budget);
+                        record.boxes = line.boxes;
+                    }
+                    (Quantity::Rarity, AxisValue::Name(name)) => record.rarity = Some(name),
+                    _ => unreachable!("{quantity} charted as {:?}", line.value),
+                }
+                record.expected_value = record.expected_value.or(line.expected_value);
+                record.expected_spend = record.expected_spend.or(line.expected_spend);
+                record.cost = record.cost.or(line.cost);
+            }
+            match chart.measure {
+                Measure::Probability => record.probability = Some(cell),
+                Measure::ExpectedValue => record.expected_value = Some(cell),
+            }
+            records.push(record);
+        }
+    }
+    records
+}
+
+#[derive(Serialize)]
+struct MultiRecord<'a> {
+    treasure: &'a str,
+    /// The targets, written the same way as on the command line and separated by spaces
+    targets: &'a str,
+    goal: &'static str,
+    expected_value: f64,
+    boxes: Option<usize>,
+    probability: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct MultiChartJson<'a> {
+    treasure: &'a str,
+    targets: &'a str,
+    goal: &'static str,
+    #[serde(flatten)]
+    chart: &'a Chart,
+}
+
+#[derive(Serialize)]
+struct JointRecord<'a> {
+    treasure: &'a str,
+    /// The tiers, written the same way as on the command line and separated by spaces
+    tiers: &'a str,
+    overlap: &'static str,
+    boxes: usize,
+    /// The probability of getting at least one item of every tier
+    all: f64,
+    /// The probability of getting at least one item of any tier
+    any: f64,
+    /// The tier this record's expected number of items is for, or common for boxes that drop none of the tiers
+    rarity: &'a str,
+    expected_drops: f64,
+}
+
+/// Prints the results of the modes that calculate with several target items
+pub struct MultiOutput<'a> {
+    pub format: Format,
+    pub treasure: &'a str,
+    /// The targets, written the same way as on the command line and separated by spaces
+    pub targets: &'a str,
+    /// The number of significant digits to round calculated probabilities and expected values to
+    pub precision: Option<usize>,
+}
+
+impl MultiOutput<'_> {
+    /// Print the expected number of boxes, and optionally the probability within `boxes` boxes, for each goal
+    pub fn results(
+        &self,
+        results: &[(Goal, f64, Option<f64>)],
+        boxes: Option<usize>,
+    ) -> Result<(), Box<dyn Error>> {
+        let round = |x| rounded(x, self.precision);
+        let results: Vec<_> = results
+            .iter()
+            .map(|&(goal, expected_value, probability)| {
+                (goal, round(expected_value), probability.map(round))
+            })
+            .collect();
+        if self.format == Format::Text {
+            for (goal, expected_value, _) in &results {
+                println!("expected boxes for {}: {expected_value}", goal_name(*goal));
+            }
+            for (goal, _, probability) in &results {
+                if let (Some(boxes), Some(probability)) = (boxes, probability) {
+                    println!(
+                        "probability of {} within {boxes} boxes: {probability}",
+                        goal_name(*goal)
+                    );
+                }
+            }
+            return Ok(());
+        }
+
+        let records: Vec<_> = results
+            .iter()
+            .map(|&(goal, expected_value, probability)| MultiRecord {
+                treasure: self.treasure,
+                targets: self.targets,
+                goal: goal_name(goal),
+                expected_value,
+                boxes,
+                probability,
+            })
+            .collect();
+        print_records(self.format, &records)
+    }
+
+    /// Print the probabilities of getting every tier and any tier, and the expected number of items of each tier, from
+    /// the joint model
+    pub fn joint(
+        &self,
+        result: &JointResult,
+        tiers: &[Target],
+        overlap: Overlap,
+    ) -> Result<(), Box<dyn Error>> {
+        let round = |x| rounded(x, self.precision);
+        let (all, any) = (round(result.all), round(result.any));
+        let expected_common = round(result.expected_common);
+        if self.format == Format::Text {
+            println!("probability of all within {} boxes: {all}", result.boxes);
+            println!("probability of any within {} boxes: {any}", result.boxes);
+            for (tier, &expected_drops) in tiers.iter().zip(&result.expected_drops) {
+                println!("expected {} items: {}", tier.rarity, round(expected_drops));
+            }
+            println!("expected common items: {expected_common}");
+            return Ok(());
+        }
+
+        let records: Vec<_> = tiers
+            .iter()
+            .map(|tier| tier.rarity.as_str())
+            .zip(result.expected_drops.iter().map(|&e| round(e)))
+            .chain([("common", expected_common)])
+            .map(|(rarity, expected_drops)| JointRecord {
+                treasure: self.treasure,
+                tiers: self.targets,
+                overlap: overlap_name(overlap),
+                boxes: result.boxes,
+                all,
+                any,
+                rarity,
+                expected_drops,
+            })
+            .collect();
+        print_records(self.format, &records)
+    }
+
+    /// Write a chart for each goal to `out`. As text, the charts are CSV grids one after another with a blank row
+    /// between them.
+    pub fn charts(&self, charts: &[(Goal, Chart)], out: &Path) -> Result<(), Box<dyn Error>> {
+        let charts: Vec<_> = charts
+            .iter()
+            .map(|(goal, chart)| (*goal, rounded_chart(chart, self.precision)))
+            .collect();
+        match self.format {
+            Format::Text => {
+                let mut wtr = Writer::from_path(out)?;
+                let mut width = None;
+                for (goal, chart) in &charts {
+                    if let Some(width) = width {
+                        wtr.write_record(std::iter::repeat_n("", width))?;
+                    }
+                    width = Some(write_chart_grid(&mut wtr, chart, goal_name(*goal), None)?);
+                }
+                wtr.flush()?;
+                Ok(())
+            }
+            Format::Json => {
+                let charts: Vec<_> = charts
+                    .iter()
+                    .map(|(goal, chart)| MultiChartJson {
+                        treasure: self.treasure,
+                        targets: self.targets,
+                        goal: goal_name(*goal),
+                        chart,
+                    })
+                    .collect();
+                write_records(File::create(out)?, self.format, &charts)
+            }
+            Format::Csv => {
+                // The rarity column holds the goal, since each goal is charted like a rarity
+                let records: Vec<_> = charts
+                    .iter()
+                    .flat_map(|(goal, chart)| {
+                        chart_records(chart, self.treasure, Some(goal_name(*goal)))
+                    })
+                    .collect();
+                write_records(File::create(out)?, self.format, &records)
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TreasureRecord<'a> {
+    treasure: &'a str,
+    description: &'a str,
+    rarity: &'a str,
+}
+
+pub fn list_treasures(treasures: &Treasures, format: Format) -> Result<(), Box<dyn Error>> {
+    if format == Format::Text {
+        for treasure in treasures.iter() {
+            if treasure.description.is_empty() {
+                println!("{}", treasure.name);
+            } else {
+                println!("{} - {}", treasure.name, treasure.description);
+            }
+            for table in treasure.tables.iter() {
+                println!("    {}", table.name);
+            }
+        }
+        return Ok(());
+    }
+
+    let records: Vec<_> = treasures
+        .iter()
+        .flat_map(|treasure| {
+            treasure.tables.iter().map(|table| TreasureRecord {
+                treasure: &treasure.name,
+                description: &treasure.description,
+                rarity: &table.name,
+            })
+        })
+        .collect();
+    print_records(format, &records)
+}
+
+#[derive(Serialize)]
+struct StatusRecord<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    /// The "1 in N" odds of the next box
+    odds: f64,
+    expected_value: f64,
+}
+
+/// Show the treasure opening each rarity is on for every treasure in the ledger, with the odds of the next box and the
+/// expected number of boxes still to open (rounded to `precision` significant digits if given)
+pub fn status(
+    ledger: &Ledger,
+    treasures: &Treasures,
+    format: Format,
+    precision: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    // Treasures recorded with odds files that weren't given this time are skipped, rather than failing every command
+    // that reads the ledger
+    let mut known = Vec::new();
+    for name in ledger.treasures() {
+        match treasures.treasure(name) {
+            Ok(treasure) => known.push(treasure),
+            Err(_) => eprintln!(
+                "Skipping {name} in the ledger, its odds aren't built in or in any odds file given"
+            ),
+        }
+    }
+
+    let mut records = Vec::new();
+    for treasure in &known {
+        let name = treasure.name.as_str();
+        for table in treasure.tables.iter() {
+            let treasure_opening = ledger.treasure_opening(name, &table.name);
+            records.push(StatusRecord {
+                treasure: &treasure.name,
+                rarity: &table.name,
+                treasure_opening,
+                odds: table.odds_from(treasure_opening).next().unwrap(),
+                expected_value: rounded(expected_value(table, treasure_opening)?, precision),
+            });
+        }
+    }
+
+    if format != Format::Text {
+        return print_records(format, &records);
+    }
+    if records.is_empty() {
+        println!("No boxes recorded yet. Record one with record open.");
+    }
+    for treasure in &known {
+        let name = treasure.name.as_str();
+        match ledger.boxes(name).count() {
+            1 => println!("{name} (1 box recorded)"),
+            count => println!("{name} ({count} boxes recorded)"),
+        }
+        for record in records.iter().filter(|record| record.treasure == name) {
+            println!(
+                "    {}: treasure opening {}, 1 in {} odds, {} more boxes expected",
+                record.rarity, record.treasure_opening, record.odds, record.expected_value
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write `chart` as a grid with `label` in its top left corner, returning the number of fields in each record
+fn write_chart_grid<W: io::Write>(
+    wtr: &mut Writer<W>,
+    chart: &Chart,
+    label: &str,
+    pricing: Option<&Pricing>,
+) -> Result<usize, Box<dyn Error>> {
+    let money = |amount: Option<f64>| match (amount, pricing) {
+        (Some(amount), Some(pricing)) => pricing.display(amount).to_string(),
+        _ => String::new(),
+    };
+    let number = |x: Option<f64>| x.map_or(String::new(), |x| x.to_string());
+
+    // Rows with expected values, and with a price expected spends, get a column for each before the cells. Columns get
+    // a row for each below their values, along with a row for their cost when a price is given.
+    let row_ev = chart.rows.iter().any(|row| row.expected_value.is_some());
+    let row_spend = pricing.is_some() && chart.rows.iter().any(|row| row.expected_spend.is_some());
+    let blank_columns = 1 + usize::from(row_ev) + usize::from(row_spend);
+    let header = |label: &str, cells: Vec<String>| {
+        std::iter::once(label.to_owned())
+            .chain(std::iter::repeat_n(String::new(), blank_columns))
+            .chain(cells)
+    };
+
+    wtr.write_record(header(
+        label,
+        chart
+            .columns
+            .iter()
+            .map(|column| column.value.to_string())
+            .collect(),
+    ))?;
+    if pricing.is_some() && chart.columns.iter().any(|column| column.cost.is_some()) {
+        wtr.write_record(header(
+            "",
+            chart
+                .columns
+                .iter()
+                .map(|column| money(column.cost))
+                .collect(),
+        ))?;
+    }
+    if chart
+        .columns
+        .iter()
+        .any(|column| column.expected_value.is_some())
+    {
+        wtr.write_record(header(
+            "expected value",
+            (chart.columns.iter())
+                .map(|column| number(column.expected_value))
+                .collect(),
+        ))?;
+    }
+    if pricing.is_some()
+        && chart
+            .columns
+            .iter()
+            .any(|column| column.expected_spend.is_some())
+    {
+        wtr.write_record(header(
+            "expected spend",
+            (chart.columns.iter())
+                .map(|column| money(column.expected_spend))
+                .collect(),
+        ))?;
+    }
+
+    for (row, cells) in chart.rows.iter().zip(&chart.cells) {
+        wtr.write_record(
+            std::iter::once(row.value.to_string())
+                .chain(row_ev.then(|| number(row.expected_value)))
+                .chain(row_spend.then(|| money(row.expected_spend)))
+                .chain([String::new()])
+                .chain(cells.iter().map(|cell| cell.to_string())),
+        )?;
+    }
+    Ok(1 + blank_columns + chart.columns.len())
+}
@@ -0,0 +1,605 @@
+//! Line plots of charts, rendered to SVG without any outside tools. With the `png` feature, plots can also be
+//! rasterized to PNG. Plots and heatmaps can also be drawn as text for a quick look in the terminal.
+
+use std::fmt::Write;
+
+use crate::chart::{AxisValue, Chart, Measure, Quantity};
+
+const WIDTH: f64 = 800.;
+const HEIGHT: f64 = 500.;
+const MARGIN_LEFT: f64 = 70.;
+const MARGIN_RIGHT: f64 = 150.;
+const MARGIN_TOP: f64 = 40.;
+const MARGIN_BOTTOM: f64 = 50.;
+
+/// The most lines drawn on a plot of a chart's cells. Charts with more rows than this have an evenly spaced selection
+/// of them plotted, so the plot stays readable.
+pub const MAX_SERIES: usize = 10;
+
+const COLORS: [&str; MAX_SERIES] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+
+/// One line of a plot
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinePlot {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub series: Vec<Series>,
+    /// The range of the y axis. Fits the data if not given.
+    pub y_range: Option<(f64, f64)>,
+}
+
+/// How an axis of a plot of `quantity` is labelled
+fn axis_label(quantity: Quantity) -> &'static str {
+    match quantity {
+        Quantity::Opening => "Treasure opening",
+        Quantity::Boxes => "Boxes opened",
+        Quantity::Budget => "Budget",
+        Quantity::Rarity => "Rarity",
+    }
+}
+
+/// The name of the line plotted for one row or column of a chart
+fn line_name(quantity: Quantity, value: &AxisValue) -> String {
+    match quantity {
+        Quantity::Opening => format!("Opening {value}"),
+        Quantity::Boxes => format!("{value} boxes"),
+        Quantity::Budget => format!("Budget {value}"),
+        Quantity::Rarity => value.to_string(),
+    }
+}
+
+/// The chart's cells against its columns, with a line for each row. Charts with rarities across are plotted against
+/// their rows instead, with a line for each rarity, since rarities can't go along the x axis.
+pub fn cell_plot(chart: &Chart, title: &str) -> LinePlot {
+    let across = chart.column_quantity != Quantity::Rarity;
+    let (x_quantity, xs, line_quantity, lines) = match across {
+        true => (
+            chart.column_quantity,
+            &chart.columns,
+            chart.row_quantity,
+            &chart.rows,
+        ),
+        false => (
+            chart.row_quantity,
+            &chart.rows,
+            chart.column_quantity,
+            &chart.columns,
+        ),
+    };
+    let cell = |line: usize, x: usize| match across {
+        true => chart.cells[line][x],
+        false => chart.cells[x][line],
+    };
+
+    LinePlot {
+        title: title.to_owned(),
+        x_label: axis_label(x_quantity).to_owned(),
+        y_label: match chart.measure {
+            Measure::Probability => "Probability",
+            Measure::ExpectedValue => "Expected boxes",
+        }
+        .to_owned(),
+        series: spread(lines.len(), MAX_SERIES)
+            .into_iter()
+            .map(|line| Series {
+                name: line_name(line_quantity, &lines[line].value),
+                points: xs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(x, value)| Some((value.value.as_f64()?, cell(line, x))))
+                    .collect(),
+            })
+            .collect(),
+        y_range: (chart.measure == Measure::Probability).then_some((0., 1.)),
+    }
+}
+
+/// The expected number of boxes needed to get the item against the starting treasure, for charts of probabilities with
+/// treasure openings on an axis
+pub fn expected_value_plot(chart: &Chart, title: &str) -> Option<LinePlot> {
+    let openings = match (chart.measure, chart.row_quantity, chart.column_quantity) {
+        (Measure::Probability, Quantity::Opening, _) => &chart.rows,
+        (Measure::Probability, _, Quantity::Opening) => &chart.columns,
+        _ => return None,
+    };
+    Some(LinePlot {
+        title: title.to_owned(),
+        x_label: "Treasure opening".to_owned(),
+        y_label: "Expected boxes".to_owned(),
+        series: vec![Series {
+            name: "Expected value".to_owned(),
+            points: openings
+                .iter()
+                .filter_map(|line| Some((line.value.as_f64()?, line.expected_value?)))
+                .collect(),
+        }],
+        y_range: None,
+    })
+}
+
+/// Round numbers that split `[min, max]` into about `count` steps, or just `min` if there's nothing to split
+fn ticks(min: f64, max: f64, count: usize) -> Vec<f64> {
+    let span = max - min;
+    if span.is_nan() || span <= 0. {
+        return vec![min];
+    }
+    let rough = span / count.max(1) as f64;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap();
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+/// The markers used to draw each series of a text plot
+const MARKERS: [char; MAX_SERIES] = ['*', '+', 'o', 'x', '#', '@', '%', '&', '=', '~'];
+
+/// Shades from least to most likely, for heatmaps drawn without color
+const SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// The color of a probability on a heatmap, going from dark blue through green to yellow, as red, green and blue
+fn heat_color(p: f64) -> (u8, u8, u8) {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (68., 1., 84.),
+        (59., 82., 139.),
+        (33., 145., 140.),
+        (94., 201., 98.),
+        (253., 231., 37.),
+    ];
+    let scaled = p.clamp(0., 1.) * (STOPS.len() - 1) as f64;
+    let i = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - i as f64;
+    let (from, to) = (STOPS[i], STOPS[i + 1]);
+    let mix = |a: f64, b: f64| (a + (b - a) * t).round() as u8;
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+/// `text` on a background of `color`, using the escape codes of terminals with 24-bit color
+fn on_color(text: &str, (r, g, b): (u8, u8, u8)) -> String {
+    format!("\x1b[48;2;{r};{g};{b}m{text}\x1b[0m")
+}
+
+/// Evenly spaced indices into `0..len`, at most `count` of them, always including the first and last
+fn spread(len: usize, count: usize) -> Vec<usize> {
+    if len <= count {
+        (0..len).collect()
+    } else if count <= 1 {
+        vec![0]
+    } else {
+        (0..count).map(|i| i * (len - 1) / (count - 1)).collect()
+    }
+}
+
+/// Draw a chart's cells as a heatmap of its rows (down) against its columns (across), fitted to `width` columns. When
+/// the chart has more columns than fit, an evenly spaced selection of them is drawn. Probabilities go from 0 to 1, and
+/// expected values from 0 to the largest finite one in the chart, with any infinite ones drawn like the largest. Without
+/// `color`, cells are drawn with shading characters instead.
+pub fn heatmap(chart: &Chart, title: &str, width: usize, color: bool) -> String {
+    let label_width = chart
+        .rows
+        .iter()
+        .map(|row| row.value.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max("open".len());
+    let available = width.saturating_sub(label_width + 3).max(1);
+    let cell_width = (available / chart.columns.len().max(1)).clamp(1, 4);
+    let columns = spread(chart.columns.len(), available / cell_width);
+    let max = match chart.measure {
+        Measure::Probability => 1.,
+        // An infinite maximum would make every cell 0 or NaN
+        Measure::ExpectedValue => (chart.cells.iter().flatten())
+            .copied()
+            .filter(|cell| cell.is_finite())
+            .fold(f64::MIN_POSITIVE, f64::max),
+    };
+
+    let mut text = String::new();
+    let _ = writeln!(text, "{title}");
+    let value =
+        |i: Option<&usize>| i.map_or(String::new(), |&i| chart.columns[i].value.to_string());
+    let first = format!("{} {}", chart.column_quantity, value(columns.first()));
+    let last = value(columns.last());
+    let span = columns.len() * cell_width;
+    let _ = writeln!(
+        text,
+        "{:>label_width$} | {first}{:>pad$}",
+        "",
+        last,
+        pad = span.saturating_sub(first.len()).max(last.len() + 1)
+    );
+
+    for (row, cells) in chart.rows.iter().zip(&chart.cells) {
+        let _ = write!(text, "{:>label_width$} | ", row.value.to_string());
+        for &i in &columns {
+            let p = (cells[i] / max).min(1.);
+            if color {
+                text.push_str(&on_color(&" ".repeat(cell_width), heat_color(p)));
+            } else {
+                let shade = SHADES[((p * SHADES.len() as f64) as usize).min(SHADES.len() - 1)];
+                text.extend(std::iter::repeat_n(shade, cell_width));
+            }
+        }
+        text.push('\n');
+    }
+
+    // A legend mapping colors or shades back to cells
+    let (low, high) = match chart.measure {
+        Measure::Probability => ("0%".to_owned(), "100%".to_owned()),
+        Measure::ExpectedValue => ("0".to_owned(), format!("{max:.1} boxes")),
+    };
+    let _ = write!(text, "{:>label_width$}   {low} ", "");
+    for (i, &shade) in SHADES.iter().enumerate() {
+        let p = (i as f64 + 0.5) / SHADES.len() as f64;
+        if color {
+            text.push_str(&on_color("  ", heat_color(p)));
+        } else {
+            text.push(shade);
+        }
+    }
+    let _ = writeln!(text, " {high}");
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The y value of a line through `points` (sorted by x) at `x`, or `None` if `x` is outside the line
+fn interpolate(points: &[(f64, f64)], x: f64) -> Option<f64> {
+    match points {
+        [] => None,
+        [(x0, y0)] => (*x0 == x).then_some(*y0),
+        _ => points.windows(2).find_map(|pair| {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            (x0 <= x && x <= x1).then(|| {
+                if x1 == x0 {
+                    y0
+                } else {
+                    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+                }
+            })
+        }),
+    }
+}
+
+impl LinePlot {
+    fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let points = || self.series.iter().flat_map(|series| &series.points);
+        let min_max = |values: Vec<f64>| {
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            match (min.is_finite(), max.is_finite()) {
+                (true, true) if min < max => (min, max),
+                (true, true) => (min - 1., max + 1.),
+                _ => (0., 1.),
+            }
+        };
+
+        let x = min_max(points().map(|p| p.0).collect());
+        let y = self
+            .y_range
+            .unwrap_or_else(|| min_max(points().map(|p| p.1).collect()));
+        (x, y)
+    }
+
+    pub fn to_svg(&self) -> String {
+        let ((x_min, x_max), (y_min, y_max)) = self.bounds();
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let sx = |x: f64| MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_width;
+        let sy = |y: f64| MARGIN_TOP + (1. - (y - y_min) / (y_max - y_min)) * plot_height;
+
+        // Writing to a String can't fail, so the results of write! are ignored throughout
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="12">"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{WIDTH}" height="{HEIGHT}" fill="white"/>"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" font-size="16">{}</text>"#,
+            MARGIN_LEFT + plot_width / 2.,
+            MARGIN_TOP / 2. + 6.,
+            escape(&self.title)
+        );
+
+        // Grid lines and tick labels
+        for x in ticks(x_min, x_max, 10) {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{0:.1}" y1="{1:.1}" x2="{0:.1}" y2="{2:.1}" stroke="#ddd"/><text x="{0:.1}" y="{3:.1}" text-anchor="middle">{4}</text>"##,
+                sx(x),
+                MARGIN_TOP,
+                MARGIN_TOP + plot_height,
+                MARGIN_TOP + plot_height + 16.,
+                x
+            );
+        }
+        for y in ticks(y_min, y_max, 8) {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{0:.1}" y1="{1:.1}" x2="{2:.1}" y2="{1:.1}" stroke="#ddd"/><text x="{3:.1}" y="{4:.1}" text-anchor="end">{5}</text>"##,
+                MARGIN_LEFT,
+                sy(y),
+                MARGIN_LEFT + plot_width,
+                MARGIN_LEFT - 6.,
+                sy(y) + 4.,
+                // Round away floating point noise such as 0.30000000000000004
+                (y * 1e6).round() / 1e6
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{MARGIN_LEFT}" y="{MARGIN_TOP}" width="{plot_width}" height="{plot_height}" fill="none" stroke="black"/>"#
+        );
+
+        // Axis labels
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            MARGIN_LEFT + plot_width / 2.,
+            HEIGHT - 12.,
+            escape(&self.x_label)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="18" y="{0}" text-anchor="middle" transform="rotate(-90 18 {0})">{1}</text>"#,
+            MARGIN_TOP + plot_height / 2.,
+            escape(&self.y_label)
+        );
+
+        // The lines themselves, with a legend to the right of the plot
+        for (i, series) in self.series.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            let points: Vec<_> = series
+                .points
+                .iter()
+                .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                points.join(" ")
+            );
+
+            let legend_y = MARGIN_TOP + 10. + i as f64 * 18.;
+            let legend_x = MARGIN_LEFT + plot_width + 12.;
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{legend_x}" y1="{legend_y}" x2="{}" y2="{legend_y}" stroke="{color}" stroke-width="2"/><text x="{}" y="{}">{}</text>"#,
+                legend_x + 20.,
+                legend_x + 26.,
+                legend_y + 4.,
+                escape(&series.name)
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Draw the plot with text characters in a box `width` columns wide and `height` rows tall (not counting the
+    /// title, axes and legend). Each series is drawn with its own marker.
+    pub fn to_text(&self, width: usize, height: usize) -> String {
+        let ((x_min, x_max), (y_min, y_max)) = self.bounds();
+        let y_label = |y: f64| format!("{:.2}", y);
+        let label_width = y_label(y_min).len().max(y_label(y_max).len());
+        let plot_width = width.saturating_sub(label_width + 2).max(10);
+        let plot_height = height.max(3);
+
+        let mut grid = vec![vec![' '; plot_width]; plot_height];
+        for (series, &marker) in self.series.iter().zip(MARKERS.iter().cycle()) {
+            let rows = (0..plot_width).map(|column| {
+                let x = x_min + (x_max - x_min) * column as f64 / (plot_width - 1) as f64;
+                let y = interpolate(&series.points, x)?;
+                let row = ((y_max - y) / (y_max - y_min) * (plot_height - 1) as f64).round();
+                (0.0..plot_height as f64)
+                    .contains(&row)
+                    .then_some(row as usize)
+            });
+            for (column, row) in rows.enumerate() {
+                if let Some(row) = row {
+                    grid[row][column] = marker;
+                }
+            }
+        }
+
+        let mut text = String::new();
+        let _ = writeln!(text, "{}", self.title);
+        for (i, row) in grid.iter().enumerate() {
+            let label = match i {
+                0 => y_label(y_max),
+                i if i == plot_height - 1 => y_label(y_min),
+                i if i == plot_height / 2 => y_label((y_min + y_max) / 2.),
+                _ => String::new(),
+            };
+            let _ = writeln!(
+                text,
+                "{label:>label_width$} |{}",
+                row.iter().collect::<String>().trim_end()
+            );
+        }
+        let _ = writeln!(text, "{:>label_width$} +{}", "", "-".repeat(plot_width));
+        let (first, last) = (x_min.to_string(), x_max.to_string());
+        let _ = writeln!(
+            text,
+            "{:>label_width$}  {first}{last:>pad$}",
+            "",
+            pad = plot_width.saturating_sub(first.len()).max(1)
+        );
+        let x_label = format!("{:>label_width$}  {:^plot_width$}", "", self.x_label);
+        let _ = writeln!(text, "{}", x_label.trim_end());
+
+        // The legend, wrapped to the plot's width
+        let mut line = String::new();
+        for (series, marker) in self.series.iter().zip(MARKERS.iter().cycle()) {
+            let entry = format!("{marker} {}", series.name);
+            if !line.is_empty() && line.len() + entry.len() + 3 > width {
+                let _ = writeln!(text, "{}", line.trim_end());
+                line.clear();
+            }
+            line.push_str(&entry);
+            line.push_str("   ");
+        }
+        if !line.is_empty() {
+            let _ = writeln!(text, "{}", line.trim_end());
+        }
+        text
+    }
+
+    /// Render the plot to a PNG image. Text is drawn with the system's fonts, so it may be missing on systems without
+    /// any.
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Result<Vec<u8>, crate::Error> {
+        use resvg::{tiny_skia, usvg};
+
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        let tree = usvg::Tree::from_str(&self.to_svg(), &options)
+            .map_err(|e| crate::Error::Render(e.to_string()))?;
+
+        let size = tree.size().to_int_size();
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| crate::Error::Render("plot has no area".to_owned()))?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        pixmap
+            .encode_png()
+            .map_err(|e| crate::Error::Render(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::ChartLine;
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn ticks_are_round_numbers() {
+        let tenths: Vec<_> = (0..=10).map(|i| i as f64 / 10.).collect();
+        assert!(close(&ticks(0., 1., 10), &tenths));
+        assert!(close(&ticks(0., 100., 8), &[0., 20., 40., 60., 80., 100.]));
+        assert!(close(
+            &ticks(3., 97., 10),
+            &[10., 20., 30., 40., 50., 60., 70., 80., 90.]
+        ));
+        assert!(close(&ticks(-1., 1., 4), &[-1., -0.5, 0., 0.5, 1.]));
+        assert!(close(&ticks(1., 1000., 1), &[1000.]));
+    }
+
+    #[test]
+    fn ticks_of_nothing_are_just_the_value() {
+        assert_eq!(ticks(3., 3., 10), [3.]);
+        assert_eq!(ticks(1e6, 1e6, 10), [1e6]);
+        assert_eq!(ticks(5., 1., 10), [5.]);
+        assert_eq!(ticks(0., 1., 0), [0., 1.]);
+    }
+
+    #[test]
+    fn spreads_keep_the_first_and_last() {
+        assert_eq!(spread(4, 10), [0, 1, 2, 3]);
+        assert_eq!(spread(11, 3), [0, 5, 10]);
+        let indices = spread(1000, MAX_SERIES);
+        assert_eq!(indices.len(), MAX_SERIES);
+        assert_eq!((indices[0], indices[MAX_SERIES - 1]), (0, 999));
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn spreads_of_one_or_none_are_the_first() {
+        assert_eq!(spread(5, 1), [0]);
+        assert_eq!(spread(5, 0), [0]);
+        assert_eq!(spread(1, 1), [0]);
+        assert_eq!(spread(0, 0), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn interpolation_is_only_within_the_line() {
+        let points = [(1., 10.), (3., 30.), (4., 0.)];
+        assert_eq!(interpolate(&points, 2.), Some(20.));
+        assert_eq!(interpolate(&points, 3.5), Some(15.));
+        assert_eq!(interpolate(&points, 1.), Some(10.));
+        assert_eq!(interpolate(&points, 4.), Some(0.));
+        assert_eq!(interpolate(&points, 0.5), None);
+        assert_eq!(interpolate(&points, 4.5), None);
+        assert_eq!(interpolate(&[(1., 10.)], 1.), Some(10.));
+        assert_eq!(interpolate(&[(1., 10.)], 2.), None);
+        assert_eq!(interpolate(&[], 1.), None);
+    }
+
+    #[test]
+    fn svg_text_is_escaped() {
+        let plot = LinePlot {
+            title: r#"<b>"Rare" & more</b>"#.to_owned(),
+            x_label: "Boxes opened".to_owned(),
+            y_label: "Probability".to_owned(),
+            series: vec![Series {
+                name: "Opening <1>".to_owned(),
+                points: vec![(1., 0.1), (2., 0.2)],
+            }],
+            y_range: Some((0., 1.)),
+        };
+        let svg = plot.to_svg();
+        assert!(
+            svg.contains("&lt;b&gt;&quot;Rare&quot; &amp; more&lt;/b&gt;"),
+            "{svg}"
+        );
+        assert!(svg.contains("Opening &lt;1&gt;"), "{svg}");
+        assert!(!svg.contains("<b>"));
+    }
+
+    fn line(value: usize) -> ChartLine {
+        ChartLine {
+            value: AxisValue::Count(value),
+            boxes: None,
+            cost: None,
+            expected_value: None,
+            expected_spend: None,
+        }
+    }
+
+    #[test]
+    fn heatmaps_of_infinite_expected_values_are_shaded_as_the_largest() {
+        let chart = Chart {
+            row_quantity: Quantity::Rarity,
+            column_quantity: Quantity::Opening,
+            measure: Measure::ExpectedValue,
+            rarity: None,
+            treasure_opening: Some(1),
+            columns: vec![line(1), line(2)],
+            rows: vec![line(1), line(2)],
+            cells: vec![vec![0., 2.], vec![4., f64::INFINITY]],
+        };
+        let text = heatmap(&chart, "title", 20, false);
+        let rows: Vec<_> = text.lines().skip(2).take(2).collect();
+        assert_eq!(rows, ["   1 |     ++++", "   2 | @@@@@@@@"], "{text}");
+        assert!(text.trim_end().ends_with(" 4.0 boxes"), "{text}");
+        assert!(!text.contains("NaN") && !text.contains("inf"), "{text}");
+    }
+}
@@ -0,0 +1,354 @@
+//! A Python extension module, so notebooks can use the same math as the command line tool. Built with maturin, see
+//! README.md. Functions take a rarity as a `Rarity`, an `OddsTable` or the name of a built-in rarity, and take either a
+//! single number or a sequence of numbers (a list, a range, a NumPy array...) for treasure openings and boxes. Results
+//! for sequences come back as `array.array("d")`, which NumPy can wrap without copying.
+
+use std::collections::HashMap;
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyList},
+};
+
+use crate::{
+    calc,
+    odds::OddsFile,
+    treasure::{Treasures, DEFAULT_TREASURE},
+    Error, OddsTable, Rarity, Tail,
+};
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        match e {
+            // FileNotFoundError, PermissionError and friends
+            Error::Io(e) => e.into(),
+            e => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// One of the built-in rarities
+#[pyclass(
+    name = "Rarity",
+    module = "dota_odds_calc",
+    eq,
+    eq_int,
+    frozen,
+    from_py_object
+)]
+#[derive(Clone, Copy, PartialEq)]
+enum PyRarity {
+    #[pyo3(name = "RARE")]
+    Rare,
+    #[pyo3(name = "VERY_RARE")]
+    VeryRare,
+    #[pyo3(name = "ULTRA_RARE")]
+    UltraRare,
+}
+
+impl From<PyRarity> for Rarity {
+    fn from(rarity: PyRarity) -> Self {
+        match rarity {
+            PyRarity::Rare => Rarity::Rare,
+            PyRarity::VeryRare => Rarity::VeryRare,
+            PyRarity::UltraRare => Rarity::UltraRare,
+        }
+    }
+}
+
+impl From<Rarity> for PyRarity {
+    fn from(rarity: Rarity) -> Self {
+        match rarity {
+            Rarity::Rare => PyRarity::Rare,
+            Rarity::VeryRare => PyRarity::VeryRare,
+            Rarity::UltraRare => PyRarity::UltraRare,
+        }
+    }
+}
+
+#[pymethods]
+impl PyRarity {
+    /// Every built-in rarity, rarest last
+    #[staticmethod]
+    fn all() -> Vec<PyRarity> {
+        Rarity::ALL.into_iter().map(PyRarity::from).collect()
+    }
+
+    /// Look up a rarity by the name it goes by on the command line, such as "very-rare"
+    #[staticmethod]
+    fn from_name(name: &str) -> PyResult<PyRarity> {
+        Rarity::from_name(name)
+            .map(PyRarity::from)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown rarity `{name}`")))
+    }
+
+    /// The name this rarity goes by on the command line and in odds files
+    #[getter]
+    fn name(&self) -> &'static str {
+        Rarity::from(*self).name()
+    }
+
+    /// The "1 in N" odds of each treasure opening, starting with the first
+    #[getter]
+    fn odds(&self) -> Vec<f64> {
+        Rarity::from(*self).odds().to_vec()
+    }
+
+    /// The built-in odds table for this rarity
+    fn table(&self) -> PyOddsTable {
+        PyOddsTable(Rarity::from(*self).table())
+    }
+
+    fn __str__(&self) -> &'static str {
+        self.name()
+    }
+}
+
+/// The escalating odds of opening an item of one rarity. `odds` are the "1 in N" odds of each treasure opening,
+/// starting with the first, and `tail` the "1 in N" odds of every opening after those, or `None` to keep using the
+/// last of `odds`.
+#[pyclass(name = "OddsTable", module = "dota_odds_calc", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyOddsTable(OddsTable);
+
+#[pymethods]
+impl PyOddsTable {
+    #[new]
+    #[pyo3(signature = (name, odds, tail = None))]
+    fn new(name: String, odds: Vec<f64>, tail: Option<f64>) -> PyResult<Self> {
+        let table = OddsTable {
+            name,
+            odds,
+            tail: tail.map_or(Tail::RepeatLast, Tail::Fixed),
+        };
+        table.validate()?;
+        Ok(PyOddsTable(table))
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[getter]
+    fn odds(&self) -> Vec<f64> {
+        self.0.odds.clone()
+    }
+
+    #[getter]
+    fn tail(&self) -> Option<f64> {
+        match self.0.tail {
+            Tail::RepeatLast => None,
+            Tail::Fixed(odds) => Some(odds),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        let odds: Vec<_> = self.0.odds.iter().map(|odds| format!("{odds:?}")).collect();
+        let tail = self
+            .tail()
+            .map_or("None".to_owned(), |tail| format!("{tail:?}"));
+        format!(
+            "OddsTable('{}', [{}], tail={tail})",
+            self.0.name,
+            odds.join(", ")
+        )
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// A rarity argument: a built-in rarity, an odds table, or the name of a built-in rarity
+#[derive(FromPyObject)]
+enum Odds {
+    Rarity(PyRarity),
+    Table(PyOddsTable),
+    Name(String),
+}
+
+impl Odds {
+    fn resolve(self) -> Result<OddsTable, Error> {
+        match self {
+            Odds::Rarity(rarity) => Ok(Rarity::from(rarity).table()),
+            Odds::Table(table) => Ok(table.0),
+            Odds::Name(name) => Ok(Treasures::builtin()
+                .treasure(DEFAULT_TREASURE)?
+                .table(&name)?
+                .clone()),
+        }
+    }
+}
+
+/// A treasure opening or number of boxes, which raises `ValueError` if it's negative (rather than the `OverflowError`
+/// of taking it as unsigned)
+fn count(n: i64) -> PyResult<usize> {
+    usize::try_from(n).map_err(|_| {
+        PyValueError::new_err(format!(
+            "Treasure openings and numbers of boxes can't be negative, got {n}"
+        ))
+    })
+}
+
+/// A treasure opening or number of boxes argument: one number, or a sequence of them
+#[derive(FromPyObject)]
+enum Counts {
+    One(i64),
+    Many(Vec<i64>),
+}
+
+impl Counts {
+    fn values(&self) -> PyResult<Vec<usize>> {
+        match self {
+            Counts::One(n) => Ok(vec![count(*n)?]),
+            Counts::Many(ns) => ns.iter().map(|&n| count(n)).collect(),
+        }
+    }
+}
+
+/// Pair up two arguments the way NumPy broadcasts them: a single number goes with every number of a sequence, and two
+/// sequences need the same length
+fn broadcast(a: &Counts, b: &Counts) -> PyResult<Vec<(usize, usize)>> {
+    let (a, b) = (a.values()?, b.values()?);
+    match (a.len(), b.len()) {
+        (1, _) => Ok(b.iter().map(|&b| (a[0], b)).collect()),
+        (_, 1) => Ok(a.iter().map(|&a| (a, b[0])).collect()),
+        (n, m) if n == m => Ok(a.iter().copied().zip(b.iter().copied()).collect()),
+        (n, m) => Err(PyValueError::new_err(format!(
+            "Sequences of different lengths ({n} and {m}) can't be paired up"
+        ))),
+    }
+}
+
+/// `values` as an `array.array("d")`
+fn array<'py>(py: Python<'py>, values: &[f64]) -> PyResult<Bound<'py, PyAny>> {
+    let bytes: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect();
+    py.import("array")?
+        .getattr("array")?
+        .call1(("d", PyBytes::new(py, &bytes)))
+}
+
+/// A single result as a float, or the results for a sequence as an array
+fn result<'py>(py: Python<'py>, many: bool, values: &[f64]) -> PyResult<Bound<'py, PyAny>> {
+    match many {
+        false => Ok(values[0].into_pyobject(py)?.into_any()),
+        true => array(py, values),
+    }
+}
+
+/// The probability of getting the item within each number of boxes from 0 to `max_boxes`
+fn cdf(table: &OddsTable, treasure_opening: usize, max_boxes: usize) -> Result<Vec<f64>, Error> {
+    let points = calc::distribution(table, treasure_opening, max_boxes)?;
+    Ok(std::iter::once(0.)
+        .chain(points.iter().map(|point| point.by))
+        .collect())
+}
+
+/// The expected number of boxes, starting with `treasure_opening`, needed to get an item of `rarity`
+#[pyfunction]
+fn expected_value<'py>(
+    py: Python<'py>,
+    rarity: Odds,
+    treasure_opening: Counts,
+) -> PyResult<Bound<'py, PyAny>> {
+    let table = rarity.resolve()?;
+    let values = treasure_opening
+        .values()?
+        .into_iter()
+        .map(|opening| calc::expected_value(&table, opening))
+        .collect::<Result<Vec<f64>, _>>()?;
+    result(py, matches!(treasure_opening, Counts::Many(_)), &values)
+}
+
+/// The probability of getting an item of `rarity` within `boxes` boxes, starting with `treasure_opening`
+#[pyfunction]
+fn probability<'py>(
+    py: Python<'py>,
+    rarity: Odds,
+    treasure_opening: Counts,
+    boxes: Counts,
+) -> PyResult<Bound<'py, PyAny>> {
+    let table = rarity.resolve()?;
+    let pairs = broadcast(&treasure_opening, &boxes)?;
+
+    // Each starting treasure's distribution only needs to be worked out once, as far as its most boxes
+    let mut most_boxes = HashMap::new();
+    for &(opening, boxes) in &pairs {
+        let most = most_boxes.entry(opening).or_insert(0);
+        *most = boxes.max(*most);
+    }
+    let cdfs = most_boxes
+        .into_iter()
+        .map(|(opening, most)| Ok((opening, cdf(&table, opening, most)?)))
+        .collect::<Result<HashMap<_, _>, Error>>()?;
+
+    let values: Vec<f64> = pairs
+        .iter()
+        .map(|(opening, boxes)| cdfs[opening][*boxes])
+        .collect();
+    let many = matches!(treasure_opening, Counts::Many(_)) || matches!(boxes, Counts::Many(_));
+    result(py, many, &values)
+}
+
+/// The probability that the item is first found in exactly each number of boxes from 0 to `max_boxes`, starting with
+/// `treasure_opening`. The first entry, for 0 boxes, is always 0, so the array can be indexed by number of boxes.
+#[pyfunction]
+fn distribution<'py>(
+    py: Python<'py>,
+    rarity: Odds,
+    treasure_opening: i64,
+    max_boxes: i64,
+) -> PyResult<Bound<'py, PyAny>> {
+    let points = calc::distribution(
+        &rarity.resolve()?,
+        count(treasure_opening)?,
+        count(max_boxes)?,
+    )?;
+    let values: Vec<f64> = std::iter::once(0.)
+        .chain(points.iter().map(|point| point.exactly))
+        .collect();
+    array(py, &values)
+}
+
+/// The probability of getting an item of `rarity` within each number of boxes from 0 to `max_boxes`, starting with
+/// `treasure_opening`
+#[pyfunction(name = "cdf")]
+fn py_cdf<'py>(
+    py: Python<'py>,
+    rarity: Odds,
+    treasure_opening: i64,
+    max_boxes: i64,
+) -> PyResult<Bound<'py, PyAny>> {
+    let cdf = cdf(
+        &rarity.resolve()?,
+        count(treasure_opening)?,
+        count(max_boxes)?,
+    )?;
+    array(py, &cdf)
+}
+
+/// The odds tables of an odds file (TOML, or JSON if the path ends in .json), in the same format as `--odds-file`
+#[pyfunction]
+fn load_odds_file<'py>(py: Python<'py>, path: std::path::PathBuf) -> PyResult<Bound<'py, PyList>> {
+    let file = OddsFile::load(&path)?;
+    PyList::new(py, file.tables.into_iter().map(PyOddsTable))
+}
+
+/// How many boxes you need to open in Dota 2 to get the item you want, with the same math as the dota-odds-calc
+/// command line tool
+#[pymodule]
+pub fn dota_odds_calc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyRarity>()?;
+    m.add_class::<PyOddsTable>()?;
+    m.add_function(wrap_pyfunction!(expected_value, m)?)?;
+    m.add_function(wrap_pyfunction!(probability, m)?)?;
+    m.add_function(wrap_pyfunction!(distribution, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cdf, m)?)?;
+    m.add_function(wrap_pyfunction!(load_odds_file, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use serde::Serialize;
+
+use crate::{odds::OddsTable, Error};
+
+/// Once the chance of getting at least this many items drops below this, stop listing larger numbers of items
+const NEGLIGIBLE: f64 = 1e-7;
+
+/// The most boxes the number of items got can be worked out for. Every box is stepped through for each number of items
+/// that could have dropped so far, so the time taken grows with the square of the boxes, and this many already takes a
+/// few seconds.
+pub const MAX_BOXES: usize = 5_000;
+
+/// One point of the distribution of the number of items got in a run of boxes
+#[derive(Serialize, Clone, Debug)]
+pub struct DropsPoint {
+    pub drops: usize,
+    /// The probability of getting exactly this many items
+    pub exactly: f64,
+    /// The probability of getting at least this many items
+    pub at_least: f64,
+}
+
+/// The probability of getting each number of items (from 0 to `num_boxes`) in `num_boxes` boxes, starting with
+/// `treasure_opening`. Unlike `distribution`, boxes don't stop at the first item: the treasure opening goes back to 1
+/// after every item, and the odds escalate again from there. At most `MAX_BOXES` boxes can be worked out.
+pub fn drops_distribution(
+    table: &OddsTable,
+    treasure_opening: usize,
+    num_boxes: usize,
+) -> Result<Vec<f64>, Error> {
+    table.check(treasure_opening)?;
+    if num_boxes > MAX_BOXES {
+        return Err(Error::TooManyBoxes(MAX_BOXES));
+    }
+
+    // Every opening past the end of the table has the same odds, so they can all share the last state
+    let states = table.odds.len() + 1;
+    let probabilities: Vec<f64> = table
+        .odds_from(1)
+        .take(states)
+        .map(|odds| 1. / odds)
+        .collect();
+
+    // by_state[opening - 1][drops] is the probability of being on that opening, having got that many items so far
+    let mut by_state = vec![vec![0.; num_boxes + 1]; states];
+    by_state[treasure_opening.min(states) - 1][0] = 1.;
+
+    for opened in 0..num_boxes {
+        let mut next = vec![vec![0.; num_boxes + 1]; states];
+        for (state, by_drops) in by_state.iter().enumerate() {
+            let p = probabilities[state];
+            // No more than `opened` items can have dropped yet
+            for (drops, &prob) in by_drops.iter().enumerate().take(opened + 1) {
+                if prob == 0. {
+                    continue;
+                }
+                // Getting the item puts us back on the first opening
+                next[0][drops + 1] += prob * p;
+                next[(state + 1).min(states - 1)][drops] += prob * (1. - p);
+            }
+        }
+        by_state = next;
+    }
+
+    Ok((0..=num_boxes)
+        .map(|drops| by_state.iter().map(|by_drops| by_drops[drops]).sum())
+        .collect())
+}
+
+/// The points of a distribution from `drops_distribution`, up to the number of items past which the chance of getting
+/// more is negligible
+pub fn drops(exactly: &[f64]) -> Vec<DropsPoint> {
+    // Summed from the most items down, so the tiny probabilities aren't lost in rounding
+    let mut at_least: Vec<f64> = exactly
+        .iter()
+        .rev()
+        .scan(0., |sum, &prob| {
+            *sum += prob;
+            Some(*sum)
+        })
+        .collect();
+    at_least.reverse();
+
+    exactly
+        .iter()
+        .zip(at_least)
+        .enumerate()
+        .take_while(|&(drops, (_, at_least))| drops == 0 || at_least >= NEGLIGIBLE)
+        .map(|(drops, (&exactly, at_least))| DropsPoint {
+            drops,
+            exactly,
+            // Rounding can push the sum just past 1
+            at_least: at_least.min(1.),
+        })
+        .collect()
+}
+
+/// The expected number of items of a distribution from `drops_distribution`
+pub fn expected_drops(exactly: &[f64]) -> f64 {
+    exactly
+        .iter()
+        .enumerate()
+        .map(|(drops, prob)| drops as f64 * prob)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc::probability, Rarity, Tail};
+
+    #[test]
+    fn at_least_one_item_is_the_single_item_probability() {
+        for rarity in Rarity::ALL {
+            let table = rarity.table();
+            let points = drops(&drops_distribution(&table, 7, 60).unwrap());
+            let expected: f64 = probability(&table, 7, 60).unwrap();
+            assert!((points[1].at_least - expected).abs() < 1e-12, "{rarity:?}");
+            assert!((points[0].exactly - (1. - expected)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn the_distribution_adds_up_to_1() {
+        let distribution = drops_distribution(&Rarity::Rare.table(), 1, 200).unwrap();
+        assert_eq!(distribution.len(), 201);
+        assert!((distribution.iter().sum::<f64>() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn constant_odds_are_binomial() {
+        // Resetting the opening changes nothing when every opening has the same odds
+        let coin = OddsTable {
+            name: "coin".to_owned(),
+            odds: vec![2.],
+            tail: Tail::RepeatLast,
+        };
+        let distribution = drops_distribution(&coin, 1, 4).unwrap();
+        assert_eq!(
+            distribution,
+            [1. / 16., 4. / 16., 6. / 16., 4. / 16., 1. / 16.]
+        );
+        assert_eq!(expected_drops(&distribution), 2.);
+    }
+
+    #[test]
+    fn escalating_odds_reset_after_each_item() {
+        // 1 in 2, then a sure thing: the item drops at least every other box
+        let table = OddsTable {
+            name: "escalating".to_owned(),
+            odds: vec![2., 1.],
+            tail: Tail::RepeatLast,
+        };
+        let points = drops(&drops_distribution(&table, 1, 2).unwrap());
+        assert_eq!(points[0].exactly, 0.);
+        // Missing the first box guarantees the second; getting it goes back to 1 in 2
+        assert_eq!(points[1].exactly, 0.5 + 0.5 * 0.5);
+        assert_eq!(points[2].exactly, 0.5 * 0.5);
+        // Starting on the sure thing
+        assert_eq!(
+            drops(&drops_distribution(&table, 2, 1).unwrap())[1].exactly,
+            1.
+        );
+    }
+
+    #[test]
+    fn negligible_numbers_of_items_are_left_out() {
+        let table = Rarity::Rare.table();
+        let exactly = drops_distribution(&table, 1, 100).unwrap();
+        let points = drops(&exactly);
+        assert!(points.len() < exactly.len());
+        assert!(points.last().unwrap().at_least >= NEGLIGIBLE);
+        assert!(exactly[points.len()..].iter().sum::<f64>() < NEGLIGIBLE);
+        // The mean still counts every number of items
+        let mean: f64 = points.iter().map(|p| p.drops as f64 * p.exactly).sum();
+        assert!((expected_drops(&exactly) - mean).abs() < 1e-5);
+    }
+
+    #[test]
+    fn runs_of_boxes_are_limited() {
+        let table = Rarity::Rare.table();
+        assert!(matches!(
+            drops_distribution(&table, 1, MAX_BOXES + 1),
+            Err(Error::TooManyBoxes(MAX_BOXES))
+        ));
+    }
+}
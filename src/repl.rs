@@ -0,0 +1,447 @@
+use std::{error::Error, str::FromStr};
+
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+
+use dota_odds_calc::{
+    chart::{chart, Axis, ChartSpec, Quantity, MAX_COUNT},
+    cost::{Bundle, Pricing},
+    expected_value, probability, quantile, summary,
+    treasure::{Treasure, Treasures},
+    OddsTable,
+};
+
+use crate::output::{print_terminal_plots, Format, Output};
+
+/// Every command the prompt understands, with its arguments and what it does
+const COMMANDS: [(&str, &str, &str); 12] = [
+    ("rarity", "<rarity>", "Set the rarity of the item you want"),
+    (
+        "opening",
+        "<treasure opening>",
+        "Set the treasure opening you're on",
+    ),
+    ("treasure", "<treasure>", "Switch to another treasure"),
+    (
+        "price",
+        "<price per box>|off",
+        "Set or clear the price of a single box",
+    ),
+    ("show", "", "Show the current settings"),
+    (
+        "ev",
+        "",
+        "Calculate the expected number of boxes to get the item",
+    ),
+    (
+        "summary",
+        "",
+        "Like ev, but also show the spread of the number of boxes",
+    ),
+    (
+        "prob",
+        "<boxes>",
+        "Calculate the probability of getting the item within a number of boxes",
+    ),
+    (
+        "quantile",
+        "<level>...",
+        "Calculate how many boxes you need to be that sure of getting the item",
+    ),
+    (
+        "chart",
+        "[rows] [columns]",
+        "Draw a chart in the terminal, with rows and columns written as for the chart mode",
+    ),
+    ("help", "", "Show this help"),
+    ("quit", "", "Leave the prompt"),
+];
+
+/// The chart drawn by `chart` when no size is given: this many starting treasures, against enough boxes to be 99%
+/// sure of getting the item from the current opening (up to `MAX_COUNT` boxes)
+const DEFAULT_CHART_TREASURES: usize = 10;
+
+/// Tab completion of commands, rarities and treasures
+struct ReplHelper {
+    rarities: Vec<String>,
+    treasures: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..];
+
+        let words: Vec<_> = line[..start].split_whitespace().collect();
+        let candidates: Vec<&str> = match words[..] {
+            [] => COMMANDS.iter().map(|(command, ..)| *command).collect(),
+            ["rarity"] => self.rarities.iter().map(String::as_str).collect(),
+            ["treasure"] => self.treasures.iter().map(String::as_str).collect(),
+            ["price"] => vec!["off"],
+            _ => vec![],
+        };
+        Ok((
+            start,
+            candidates
+                .into_iter()
+                .filter(|candidate| candidate.starts_with(word))
+                .map(str::to_owned)
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// What the prompt is currently calculating with
+pub struct Session<'a> {
+    pub treasures: &'a Treasures,
+    pub treasure: &'a Treasure,
+    pub rarity: Option<String>,
+    pub treasure_opening: usize,
+    pub pricing: Option<Pricing>,
+    /// Kept so that bundles and the currency still apply when the price is changed
+    pub bundles: Vec<Bundle>,
+    pub currency: String,
+    pub precision: Option<usize>,
+}
+
+/// Read the next argument of a command
+fn arg<T>(args: &mut std::str::SplitWhitespace, name: &str) -> Result<T, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: Error + 'static,
+{
+    Ok(args.next().ok_or(format!("Missing {name}"))?.parse()?)
+}
+
+impl<'a> Session<'a> {
+    fn prompt(&self) -> String {
+        match &self.rarity {
+            Some(rarity) => format!("{rarity} {}> ", self.treasure_opening),
+            None => "> ".to_owned(),
+        }
+    }
+
+    fn helper(&self) -> ReplHelper {
+        ReplHelper {
+            rarities: self.treasure.tables.names().map(str::to_owned).collect(),
+            treasures: self.treasures.names().map(str::to_owned).collect(),
+        }
+    }
+
+    fn table(&self) -> Result<(&OddsTable, Output<'_>), Box<dyn Error>> {
+        let rarity = self
+            .rarity
+            .as_deref()
+            .ok_or("Set the rarity of the item you want first, with rarity <rarity>")?;
+        let output = Output {
+            format: Format::Text,
+            treasure: &self.treasure.name,
+            rarity,
+            treasure_opening: self.treasure_opening,
+            pricing: self.pricing.as_ref(),
+            precision: self.precision,
+        };
+        Ok((self.treasure.table(rarity)?, output))
+    }
+
+    /// Run one line typed at the prompt. Returns false when it's time to leave.
+    fn execute(&mut self, line: &str) -> Result<bool, Box<dyn Error>> {
+        let mut args = line.split_whitespace();
+        let Some(command) = args.next() else {
+            return Ok(true);
+        };
+
+        match command {
+            "rarity" => {
+                let rarity: String = arg(&mut args, "rarity")?;
+                self.treasure.table(&rarity)?;
+                self.rarity = Some(rarity);
+            }
+            "opening" => {
+                let treasure_opening = arg(&mut args, "treasure opening")?;
+                if treasure_opening == 0 {
+                    return Err(dota_odds_calc::Error::InvalidTreasureOpening(0).into());
+                }
+                self.treasure_opening = treasure_opening;
+            }
+            "treasure" => {
+                let treasure = self
+                    .treasures
+                    .treasure(&arg::<String>(&mut args, "treasure")?)?;
+                // The rarity might not exist in the new treasure, so check it before switching
+                if let Some(rarity) = &self.rarity {
+                    treasure.table(rarity)?;
+                }
+                self.treasure = treasure;
+            }
+            "price" => {
+                self.pricing = match args.next() {
+                    Some("off") => None,
+                    Some(price) => Some(Pricing::new(
+                        price.parse()?,
+                        self.bundles.clone(),
+                        self.currency.clone(),
+                    )?),
+                    None => return Err("Missing price per box".into()),
+                };
+            }
+            "show" => {
+                println!("treasure: {}", self.treasure.name);
+                println!("rarity: {}", self.rarity.as_deref().unwrap_or("(not set)"));
+                println!("treasure opening: {}", self.treasure_opening);
+                match &self.pricing {
+                    Some(pricing) => {
+                        println!("price per box: {}", pricing.display(pricing.price_per_box))
+                    }
+                    None => println!("price per box: (not set)"),
+                }
+            }
+            "ev" | "summary" => {
+                let (table, output) = self.table()?;
+                let exp = expected_value::<f64>(table, self.treasure_opening)?;
+                let summary = (command == "summary")
+                    .then(|| summary(table, self.treasure_opening))
+                    .transpose()?;
+                let spend = self
+                    .pricing
+                    .as_ref()
+                    .map(|pricing| pricing.expected_spend(table, self.treasure_opening))
+                    .transpose()?;
+                output.expected_value(exp, summary.as_ref(), spend)?;
+            }
+            "prob" => {
+                let (table, output) = self.table()?;
+                let num_boxes = arg(&mut args, "number of boxes")?;
+                output.probability(
+                    num_boxes,
+                    None,
+                    probability::<f64>(table, self.treasure_opening, num_boxes)?,
+                )?;
+            }
+            "quantile" => {
+                let (table, output) = self.table()?;
+                let quantiles = args
+                    .map(|level| {
+                        let level = level.parse()?;
+                        Ok((level, quantile(table, self.treasure_opening, level)?))
+                    })
+                    .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+                if quantiles.is_empty() {
+                    return Err("Missing confidence level".into());
+                }
+                output.quantiles(&quantiles)?;
+            }
+            "chart" => {
+                let (table, output) = self.table()?;
+                let rows = match args.next() {
+                    Some(rows) => Axis::parse(rows, Quantity::Opening)?,
+                    None => Axis::Openings((1..=DEFAULT_CHART_TREASURES).collect()),
+                };
+                let columns = match args.next() {
+                    Some(columns) => Axis::parse(columns, Quantity::Boxes)?,
+                    None => {
+                        // Capped like the ranges of the chart mode, for tables with very long odds
+                        let max_boxes = quantile(table, self.treasure_opening, 0.99)?
+                            .map_or(MAX_COUNT, |boxes| boxes.min(MAX_COUNT));
+                        Axis::Boxes((1..=max_boxes).collect())
+                    }
+                };
+                let spec = ChartSpec {
+                    rows,
+                    columns,
+                    rarity: Some(output.rarity.to_owned()),
+                    treasure_opening: self.treasure_opening,
+                };
+                let chart = chart(self.treasure, &spec, self.pricing.as_ref())?;
+                print_terminal_plots(&chart, &format!("{} ({})", output.rarity, output.treasure));
+            }
+            "help" => {
+                for (command, args, help) in COMMANDS {
+                    println!("{:<40}{help}", format!("{command} {args}"));
+                }
+            }
+            "quit" | "exit" => return Ok(false),
+            _ => {
+                return Err(
+                    format!("Unknown command {command}. Type help to see the commands.").into(),
+                )
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Read commands from a prompt until the user leaves. Mistakes are printed, and the prompt carries on.
+pub fn run(mut session: Session) -> Result<(), Box<dyn Error>> {
+    let mut editor = Editor::<ReplHelper, DefaultHistory>::new()?;
+    editor.set_helper(Some(session.helper()));
+    println!("Type help to see the commands, or quit to leave.");
+
+    loop {
+        let line = match editor.readline(&session.prompt()) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        editor.add_history_entry(line.as_str())?;
+
+        match session.execute(&line) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => eprintln!("{e}"),
+        }
+        // The treasure might have changed, and with it the rarities to complete
+        editor.set_helper(Some(session.helper()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dota_odds_calc::{odds::OddsFile, Tail};
+
+    use super::*;
+
+    /// The built-in treasure, and a cache that only drops arcanas
+    fn treasures() -> Treasures {
+        let mut treasures = Treasures::builtin();
+        treasures.add_file(OddsFile {
+            treasure: Some("cache".to_owned()),
+            description: None,
+            tables: vec![OddsTable {
+                name: "arcana".to_owned(),
+                odds: vec![1e9],
+                tail: Tail::RepeatLast,
+            }],
+        });
+        treasures
+    }
+
+    fn session(treasures: &Treasures) -> Session<'_> {
+        Session {
+            treasures,
+            treasure: treasures.treasure("standard").unwrap(),
+            rarity: None,
+            treasure_opening: 1,
+            pricing: None,
+            bundles: vec![],
+            currency: "$".to_owned(),
+            precision: None,
+        }
+    }
+
+    fn error(session: &mut Session, line: &str) -> String {
+        match session.execute(line) {
+            Ok(_) => panic!("{line} didn't fail"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn settings_are_parsed() {
+        let treasures = treasures();
+        let mut session = session(&treasures);
+        assert!(session.execute("  ").unwrap());
+        assert!(session.execute("rarity ultra-rare").unwrap());
+        assert!(session.execute("opening 12").unwrap());
+        assert!(session.execute("price 2.5").unwrap());
+        assert_eq!(session.rarity.as_deref(), Some("ultra-rare"));
+        assert_eq!(session.treasure_opening, 12);
+        assert_eq!(session.pricing.as_ref().unwrap().price_per_box, 2.5);
+        assert_eq!(session.prompt(), "ultra-rare 12> ");
+
+        assert!(session.execute("price off").unwrap());
+        assert!(session.pricing.is_none());
+        assert!(!session.execute("quit").unwrap());
+    }
+
+    #[test]
+    fn mistakes_leave_the_settings_alone() {
+        let treasures = treasures();
+        let mut session = session(&treasures);
+        assert!(error(&mut session, "ev").starts_with("Set the rarity"));
+        session.execute("rarity rare").unwrap();
+
+        assert_eq!(
+            error(&mut session, "opening 0"),
+            dota_odds_calc::Error::InvalidTreasureOpening(0).to_string()
+        );
+        assert!(session.execute("opening 7").is_ok());
+        assert!(session.execute("opening seven").is_err());
+        assert!(session.execute("opening -1").is_err());
+        assert!(session.execute("rarity mythical").is_err());
+        assert!(session.execute("treasure nothing").is_err());
+        assert!(session.execute("price -1").is_err());
+        assert!(session.execute("prob many").is_err());
+        assert!(session.execute("quantile 1.5").is_err());
+        assert_eq!(session.rarity.as_deref(), Some("rare"));
+        assert_eq!(session.treasure_opening, 7);
+        assert!(session.pricing.is_none());
+
+        assert!(error(&mut session, "roll").starts_with("Unknown command roll"));
+    }
+
+    #[test]
+    fn treasures_without_the_rarity_are_not_switched_to() {
+        let treasures = treasures();
+        let mut session = session(&treasures);
+        session.execute("rarity rare").unwrap();
+        let error = error(&mut session, "treasure cache");
+        assert!(
+            error.contains("rare") && error.contains("arcana"),
+            "{error}"
+        );
+        assert_eq!(session.treasure.name, "standard");
+
+        session.rarity = None;
+        session.execute("treasure cache").unwrap();
+        session.execute("rarity arcana").unwrap();
+        assert_eq!(session.treasure.name, "cache");
+        assert!(session.execute("treasure standard").is_err());
+    }
+
+    #[test]
+    fn missing_arguments_are_named() {
+        let treasures = treasures();
+        let mut session = session(&treasures);
+        assert_eq!(error(&mut session, "rarity"), "Missing rarity");
+        assert_eq!(error(&mut session, "opening"), "Missing treasure opening");
+        assert_eq!(error(&mut session, "treasure"), "Missing treasure");
+        assert_eq!(error(&mut session, "price"), "Missing price per box");
+        session.execute("rarity rare").unwrap();
+        assert_eq!(error(&mut session, "prob"), "Missing number of boxes");
+        assert_eq!(error(&mut session, "quantile"), "Missing confidence level");
+    }
+
+    #[test]
+    fn charts_of_long_odds_are_capped() {
+        let treasures = treasures();
+        let mut session = session(&treasures);
+        session.execute("treasure cache").unwrap();
+        session.execute("rarity arcana").unwrap();
+        // Being 99% sure of an arcana takes billions of boxes
+        let table = session.treasure.table("arcana").unwrap();
+        assert!(quantile(table, 1, 0.99).unwrap().unwrap() > MAX_COUNT);
+        assert!(session.execute("chart 2").unwrap());
+        assert!(session.execute("chart opening=1..2 x").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! A local HTTP server that answers the calculator's questions as JSON, for websites and bots that would otherwise run
+//! the command line tool
+
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::{
+    calc::{expected_value, probability},
+    chart::{chart, Axis, Chart, ChartSpec, Quantity},
+    treasure::{Treasures, DEFAULT_TREASURE},
+    Error,
+};
+
+/// The most cells (rows times columns) a chart can have, so one request can't tie the server up. Probabilities are
+/// summed box by box up to the most boxes charted, once for every row or column that isn't a number of boxes, so those
+/// rows or columns times the most boxes count against this too.
+pub const MAX_CHART_CELLS: usize = 100_000;
+
+/// The most boxes a request can ask about. Requests are answered one at a time and take longer the more boxes they're
+/// for, so this keeps one request from holding up everyone else's.
+pub const MAX_BOXES: usize = 100_000;
+
+/// The largest treasure opening a request can start at
+pub const MAX_TREASURE_OPENING: usize = 100_000;
+
+/// The parameters of a request, from its query string or its JSON body. Each endpoint uses the ones it needs.
+#[derive(Deserialize, Debug)]
+struct Params {
+    treasure: Option<String>,
+    rarity: Option<String>,
+    treasure_opening: Option<usize>,
+    boxes: Option<usize>,
+    max_treasures: Option<usize>,
+    max_boxes: Option<usize>,
+    rows: Option<String>,
+    columns: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ExpectedValueResponse<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    expected_value: f64,
+}
+
+#[derive(Serialize)]
+struct ProbabilityResponse<'a> {
+    treasure: &'a str,
+    rarity: &'a str,
+    treasure_opening: usize,
+    boxes: usize,
+    probability: f64,
+}
+
+#[derive(Serialize)]
+struct ChartResponse<'a> {
+    treasure: &'a str,
+    #[serde(flatten)]
+    chart: Chart,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// A response that went wrong, with its status code and message
+struct Failure(u16, String);
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        let status = match e {
+            Error::UnknownTreasure { .. } | Error::UnknownRarity { .. } => 404,
+            _ => 400,
+        };
+        Failure(status, e.to_string())
+    }
+}
+
+/// Answers requests for the treasures it was started with
+pub struct Server {
+    http: tiny_http::Server,
+    treasures: Treasures,
+}
+
+impl Server {
+    /// Start listening on `addr`, such as `127.0.0.1:8080`. Port 0 picks any free port.
+    pub fn bind(addr: &str, treasures: Treasures) -> Result<Self, Error> {
+        let http = tiny_http::Server::http(addr).map_err(|e| Error::Serve(e.to_string()))?;
+        Ok(Server { http, treasures })
+    }
+
+    /// The address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.http.server_addr().to_ip().unwrap()
+    }
+
+    /// Answer requests one at a time, forever
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.answer(request);
+        }
+    }
+
+    fn answer(&self, mut request: Request) {
+        let mut body = String::new();
+        let (status, json) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => match self.respond(request.method(), request.url(), &body) {
+                Ok(json) => (200, json),
+                Err(Failure(status, error)) => (status, error_json(error)),
+            },
+            Err(e) => (400, error_json(format!("Could not read request: {e}"))),
+        };
+        let response = Response::from_string(json)
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        // A client that hangs up before getting its answer doesn't stop the server
+        let _ = request.respond(response);
+    }
+
+    /// The JSON answer to a request for `url`
+    fn respond(&self, method: &Method, url: &str, body: &str) -> Result<String, Failure> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        if !matches!(path, "/expected-value" | "/probability" | "/chart") {
+            return Err(Failure(404, format!("No endpoint at {path}")));
+        }
+
+        // GET requests take their parameters from the query string, and POST requests from a JSON body
+        let params: Params = match method {
+            Method::Get => serde_urlencoded::from_str(query)
+                .map_err(|e| Failure(400, format!("Invalid parameters: {e}")))?,
+            Method::Post => serde_json::from_str(body)
+                .map_err(|e| Failure(400, format!("Invalid parameters: {e}")))?,
+            _ => {
+                return Err(Failure(
+                    405,
+                    format!("{path} only takes GET and POST requests"),
+                ))
+            }
+        };
+        let required = |value: Option<usize>, name: &str| {
+            value.ok_or_else(|| Failure(400, format!("Missing parameter `{name}`")))
+        };
+        let at_most = |value: usize, max: usize, name: &str| match value <= max {
+            true => Ok(value),
+            false => Err(Failure(
+                400,
+                format!("`{name}` can be at most {max}, got {value}"),
+            )),
+        };
+
+        let treasure = self
+            .treasures
+            .treasure(params.treasure.as_deref().unwrap_or(DEFAULT_TREASURE))?;
+        let rarity = || {
+            params
+                .rarity
+                .as_deref()
+                .ok_or_else(|| Failure(400, "Missing parameter `rarity`".to_owned()))
+        };
+        let treasure_opening = at_most(
+            params.treasure_opening.unwrap_or(1),
+            MAX_TREASURE_OPENING,
+            "treasure_opening",
+        )?;
+
+        let json = match path {
+            "/expected-value" => serde_json::to_string(&ExpectedValueResponse {
+                treasure: &treasure.name,
+                rarity: rarity()?,
+                treasure_opening,
+                expected_value: expected_value(treasure.table(rarity()?)?, treasure_opening)?,
+            }),
+            "/probability" => {
+                let boxes = at_most(required(params.boxes, "boxes")?, MAX_BOXES, "boxes")?;
+                serde_json::to_string(&ProbabilityResponse {
+                    treasure: &treasure.name,
+                    rarity: rarity()?,
+                    treasure_opening,
+                    boxes,
+                    probability: probability(treasure.table(rarity()?)?, treasure_opening, boxes)?,
+                })
+            }
+            _ => {
+                // Axes in the same form as on the command line, or else the usual starting treasures against boxes
+                let axis = |axis: &Option<String>, max: Option<usize>, name: &str, default| {
+                    let axis = match axis {
+                        Some(axis) => axis.clone(),
+                        None => required(max, name)?.to_string(),
+                    };
+                    Ok::<_, Failure>(Axis::parse(&axis, default)?)
+                };
+                let spec = ChartSpec {
+                    rows: axis(
+                        &params.rows,
+                        params.max_treasures,
+                        "max_treasures",
+                        Quantity::Opening,
+                    )?,
+                    columns: axis(
+                        &params.columns,
+                        params.max_boxes,
+                        "max_boxes",
+                        Quantity::Boxes,
+                    )?,
+                    rarity: params.rarity.clone(),
+                    treasure_opening,
+                };
+                // Ranges are limited to MAX_RANGE_VALUES values while they're parsed, so the axes are small enough
+                // to size up here. Their values go from smallest to largest.
+                let mut summed = 0;
+                for (axis, other) in [(&spec.rows, &spec.columns), (&spec.columns, &spec.rows)] {
+                    match axis {
+                        Axis::Openings(openings) => {
+                            if let Some(&opening) = openings.last() {
+                                at_most(opening, MAX_TREASURE_OPENING, "treasure_opening")?;
+                            }
+                        }
+                        Axis::Boxes(boxes) => {
+                            if let Some(&most) = boxes.last() {
+                                at_most(most, MAX_BOXES, "boxes")?;
+                                summed = other.len().saturating_mul(most);
+                            }
+                        }
+                        Axis::Budgets(_) | Axis::Rarities(_) => {}
+                    }
+                }
+                let cells = spec.rows.len().saturating_mul(spec.columns.len());
+                if cells.max(summed) > MAX_CHART_CELLS {
+                    return Err(Failure(
+                        400,
+                        format!(
+                            "Charts can have at most {MAX_CHART_CELLS} cells, counting each box up to the most boxes"
+                        ),
+                    ));
+                }
+                serde_json::to_string(&ChartResponse {
+                    treasure: &treasure.name,
+                    chart: chart(treasure, &spec, None)?,
+                })
+            }
+        };
+        Ok(json.unwrap())
+    }
+}
+
+fn error_json(error: String) -> String {
+    serde_json::to_string(&ErrorResponse { error }).unwrap()
+}
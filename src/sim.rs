@@ -0,0 +1,228 @@
+use crate::{odds::OddsTable, Error};
+
+/// How many standard errors a simulated result can be from the analytic one before we call it a disagreement. This is
+/// well past what chance alone would explain, even when checking a few hundred numbers at once.
+pub const DISAGREEMENT_Z: f64 = 4.;
+
+/// The z-score for a two-sided 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// A small, seedable random number generator (xoshiro256++), so simulations can be reproduced exactly
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Spread the seed out over the whole state with splitmix64, as recommended by the xoshiro authors
+        let mut seed = seed;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s0.wrapping_add(*s3).rotate_left(23).wrapping_add(*s0);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// A uniformly distributed number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1. / (1u64 << 53) as f64)
+    }
+}
+
+/// Open boxes starting at `treasure_opening` until the item drops, `trials` times, and return how many boxes each
+/// trial took
+pub fn simulate(
+    table: &OddsTable,
+    treasure_opening: usize,
+    trials: usize,
+    seed: u64,
+) -> Result<Vec<usize>, Error> {
+    table.check(treasure_opening)?;
+    if trials < 2 {
+        return Err(Error::NotEnoughTrials(trials));
+    }
+
+    let mut rng = Rng::new(seed);
+    Ok((0..trials)
+        .map(|_| {
+            table
+                .odds_from(treasure_opening)
+                .position(|odds| rng.next_f64() < 1. / odds)
+                .unwrap()
+                + 1
+        })
+        .collect())
+}
+
+/// A number estimated by simulation, along with how well the simulation pins it down
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    pub value: f64,
+    /// The standard error of `value`
+    pub std_err: f64,
+}
+
+impl Estimate {
+    /// The 95% confidence interval around the estimate
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        (
+            self.value - CONFIDENCE_Z * self.std_err,
+            self.value + CONFIDENCE_Z * self.std_err,
+        )
+    }
+
+    /// Whether `expected` is too far from the estimate to be explained by chance
+    pub fn disagrees_with(&self, expected: f64) -> bool {
+        if self.std_err == 0. {
+            self.value != expected
+        } else {
+            (self.value - expected).abs() / self.std_err > DISAGREEMENT_Z
+        }
+    }
+}
+
+/// The mean number of boxes the simulated trials took
+pub fn mean(boxes: &[usize]) -> Estimate {
+    let n = boxes.len() as f64;
+    let mean = boxes.iter().map(|&b| b as f64).sum::<f64>() / n;
+    let variance = boxes
+        .iter()
+        .map(|&b| (b as f64 - mean).powi(2))
+        .sum::<f64>()
+        / (n - 1.).max(1.);
+    Estimate {
+        value: mean,
+        std_err: (variance / n).sqrt(),
+    }
+}
+
+/// The fraction of simulated trials that got the item within each number of boxes that has an `expected` probability
+/// (starting from 1 box). The standard errors are those of a fraction with the `expected` probability, so that a
+/// disagreement means the simulation and `expected` don't fit each other.
+pub fn cumulative_fractions(boxes: &[usize], expected: &[f64]) -> Vec<Estimate> {
+    let n = boxes.len() as f64;
+    let mut counts = vec![0usize; expected.len() + 1];
+    for &b in boxes {
+        if b <= expected.len() {
+            counts[b] += 1;
+        }
+    }
+
+    let mut within = 0;
+    expected
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            within += counts[i + 1];
+            Estimate {
+                value: within as f64 / n,
+                std_err: (p * (1. - p) / n).sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calc::{expected_value, probability},
+        Tail,
+    };
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let stream = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..100).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(stream(7), stream(7));
+        assert_ne!(stream(7), stream(8));
+
+        let mut rng = Rng::new(0);
+        assert!((0..10_000).all(|_| (0. ..1.).contains(&rng.next_f64())));
+    }
+
+    #[test]
+    fn mean_and_standard_error() {
+        let mean = mean(&[1, 2, 3]);
+        assert_eq!(mean.value, 2.);
+        // A sample variance of 1 over 3 trials
+        assert!((mean.std_err - (1f64 / 3.).sqrt()).abs() < 1e-15);
+        assert_eq!(
+            mean.confidence_interval(),
+            (2. - 1.96 * mean.std_err, 2. + 1.96 * mean.std_err)
+        );
+    }
+
+    #[test]
+    fn fractions_count_the_trials_within_each_number_of_boxes() {
+        // The trial that took 5 boxes is past every number of boxes asked about
+        let fractions = cumulative_fractions(&[1, 2, 2, 5], &[0.5, 0.5, 0.5]);
+        let values: Vec<f64> = fractions.iter().map(|fraction| fraction.value).collect();
+        assert_eq!(values, [0.25, 0.75, 0.75]);
+        assert!(fractions.iter().all(|fraction| fraction.std_err == 0.25));
+    }
+
+    #[test]
+    fn disagreeing_takes_more_than_chance() {
+        let estimate = Estimate {
+            value: 10.,
+            std_err: 1.,
+        };
+        assert!(!estimate.disagrees_with(10. + DISAGREEMENT_Z));
+        assert!(estimate.disagrees_with(10. + DISAGREEMENT_Z + 0.01));
+        // Without any spread, only the exact value agrees
+        let certain = Estimate {
+            value: 1.,
+            std_err: 0.,
+        };
+        assert!(!certain.disagrees_with(1.));
+        assert!(certain.disagrees_with(0.999));
+    }
+
+    #[test]
+    fn simulations_are_reproducible_and_agree_with_the_calculation() {
+        let table = OddsTable {
+            name: "small".to_owned(),
+            odds: vec![8., 4., 2.],
+            tail: Tail::RepeatLast,
+        };
+        let boxes = simulate(&table, 1, 20_000, 42).unwrap();
+        assert_eq!(boxes, simulate(&table, 1, 20_000, 42).unwrap());
+
+        let expected: f64 = expected_value(&table, 1).unwrap();
+        assert!(!mean(&boxes).disagrees_with(expected));
+        let probabilities: Vec<f64> = (1..=6)
+            .map(|n| probability(&table, 1, n).unwrap())
+            .collect();
+        let fractions = cumulative_fractions(&boxes, &probabilities);
+        for (fraction, &p) in fractions.iter().zip(&probabilities) {
+            assert!(!fraction.disagrees_with(p), "{} vs {p}", fraction.value);
+        }
+
+        assert!(matches!(
+            simulate(&table, 1, 1, 42),
+            Err(Error::NotEnoughTrials(1))
+        ));
+        assert!(simulate(&table, 0, 10, 42).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! Special functions for the statistics behind estimating odds tables from logged openings, and testing them against
+//! logged openings
+
+/// The natural log of the gamma function, for `x > 0`, using the Lanczos approximation (accurate to about 15 digits)
+pub fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // The reflection formula, since the approximation is only good for x >= 0.5
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1. - x);
+    }
+
+    let x = x - 1.;
+    let t = x + G + 0.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.));
+    0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The natural log of the beta function
+pub fn ln_beta(a: f64, b: f64) -> f64 {
+    ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+}
+
+/// How close successive steps of the continued fractions have to get before they're considered converged
+const EPSILON: f64 = 1e-14;
+/// Give up on a continued fraction after this many steps. They converge in far fewer for any sensible input.
+const MAX_ITERATIONS: usize = 1000;
+/// Stands in for 0 in the continued fractions, to avoid dividing by it
+const TINY: f64 = 1e-300;
+
+/// The regularized incomplete beta function I_x(a, b): the probability that a Beta(a, b) variable is at most `x`
+pub fn beta_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+
+    // The continued fraction converges quickly below the mean; above it, use the symmetry I_x(a, b) = 1 - I_1-x(b, a)
+    if x > (a + 1.) / (a + b + 2.) {
+        return 1. - beta_cdf(1. - x, b, a);
+    }
+
+    let front = (a * x.ln() + b * (1. - x).ln() - ln_beta(a, b)).exp() / a;
+    front * beta_continued_fraction(x, a, b)
+}
+
+/// The continued fraction for the incomplete beta function, evaluated with the modified Lentz method
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    let mut c = 1.;
+    let mut d = 1. - (a + b) * x / (a + 1.);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1. / d;
+    let mut result = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        // Each step of the fraction has an even term and an odd term
+        for numerator in [
+            m * (b - m) * x / ((a + 2. * m - 1.) * (a + 2. * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2. * m) * (a + 2. * m + 1.)),
+        ] {
+            d = 1. + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1. + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1. / d;
+            result *= d * c;
+        }
+        if (d * c - 1.).abs() < EPSILON {
+            break;
+        }
+    }
+    result
+}
+
+/// The value a Beta(a, b) variable is at most with probability `p`, found by bisection
+pub fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let (mut low, mut high) = (0., 1.);
+    // Bisecting 100 times pins the value down far past the precision of an f64
+    for _ in 0..100 {
+        let mid = (low + high) / 2.;
+        if beta_cdf(mid, a, b) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.
+}
+
+/// The regularized upper incomplete gamma function Q(a, x) = Γ(a, x) / Γ(a)
+pub fn gamma_upper(a: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 1.;
+    }
+    if x.is_infinite() {
+        return 0.;
+    }
+
+    // The series converges quickly below a + 1, and the continued fraction above it
+    if x < a + 1. {
+        let mut term = 1. / a;
+        let mut sum = term;
+        for n in 1..=MAX_ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1. - sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+    } else {
+        // The modified Lentz method again
+        let mut b = x + 1. - a;
+        let mut c = 1. / TINY;
+        let mut d = 1. / b;
+        let mut result = d;
+        for n in 1..=MAX_ITERATIONS {
+            let n = n as f64;
+            let numerator = -n * (n - a);
+            b += 2.;
+            d = numerator * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1. / d;
+            result *= d * c;
+            if (d * c - 1.).abs() < EPSILON {
+                break;
+            }
+        }
+        result * (-x + a * x.ln() - ln_gamma(a)).exp()
+    }
+}
+
+/// The probability that a chi-square variable with `degrees_of_freedom` is at least `x`
+pub fn chi_square_survival(x: f64, degrees_of_freedom: usize) -> f64 {
+    if degrees_of_freedom == 0 {
+        return 1.;
+    }
+    gamma_upper(degrees_of_freedom as f64 / 2., x / 2.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance * b.abs().max(1.)
+    }
+
+    #[test]
+    fn ln_gamma_matches_reference_values() {
+        for (x, expected) in [
+            (0.1, 2.252_712_651_734_205_5),
+            (0.5, 0.572_364_942_924_700_4),
+            (1., 0.),
+            (2., 0.),
+            // ln(9!)
+            (10., 12.801_827_480_081_467),
+            (100.5, 361.435_540_467_777_6),
+        ] {
+            assert!(
+                close(ln_gamma(x), expected, 1e-13),
+                "ln_gamma({x}) = {}",
+                ln_gamma(x)
+            );
+        }
+    }
+
+    #[test]
+    fn beta_cdf_matches_closed_forms() {
+        // Beta(1, 1) is uniform
+        for x in [0., 0.2, 0.5, 0.9, 1.] {
+            assert!(close(beta_cdf(x, 1., 1.), x, 1e-12));
+        }
+        // For whole numbers it's a binomial tail: I_0.3(2, 3) = P(at least 2 of 4 trials succeed)
+        assert!(close(beta_cdf(0.3, 2., 3.), 0.3483, 1e-12));
+        assert!(close(beta_cdf(0.5, 2., 2.), 0.5, 1e-12));
+        assert!(close(
+            beta_cdf(0.7, 3., 2.),
+            1. - beta_cdf(0.3, 2., 3.),
+            1e-12
+        ));
+    }
+
+    #[test]
+    fn beta_quantile_inverts_the_cdf() {
+        // Jeffreys' prior has a CDF of 2 / pi * asin(sqrt(x)), so its quantiles are sin^2(pi * p / 2)
+        assert!(close(
+            beta_quantile(0.25, 0.5, 0.5),
+            0.146_446_609_406_726_24,
+            1e-12
+        ));
+        assert!(close(beta_quantile(0.5, 0.5, 0.5), 0.5, 1e-12));
+        assert!(close(beta_quantile(0.3483, 2., 3.), 0.3, 1e-12));
+    }
+
+    #[test]
+    fn gamma_upper_matches_the_exponential() {
+        // Q(1, x) = e^-x, on both sides of where the series hands over to the continued fraction
+        for x in [0.1, 1., 1.9, 2., 5., 30.] {
+            assert!(close(gamma_upper(1., x), (-x).exp(), 1e-12), "Q(1, {x})");
+        }
+        assert_eq!(gamma_upper(3., 0.), 1.);
+        assert_eq!(gamma_upper(3., f64::INFINITY), 0.);
+    }
+
+    #[test]
+    fn chi_square_survival_matches_reference_values() {
+        // The 95th percentiles of chi-square with 1 and 10 degrees of freedom
+        assert!(close(
+            chi_square_survival(3.841_458_820_694_124, 1),
+            0.05,
+            1e-10
+        ));
+        assert!(close(
+            chi_square_survival(18.307_038_053_275_146, 10),
+            0.05,
+            1e-10
+        ));
+        // With 1 degree of freedom it's the two-sided normal tail: P(|Z| >= 2) = erfc(sqrt(2))
+        assert!(close(
+            chi_square_survival(4., 1),
+            0.045_500_263_896_358_4,
+            1e-12
+        ));
+        // With 2 it's e^(-x / 2)
+        assert!(close(
+            chi_square_survival(4., 2),
+            0.135_335_283_236_612_7,
+            1e-12
+        ));
+        assert_eq!(chi_square_survival(4., 0), 1.);
+    }
+}
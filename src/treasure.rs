@@ -0,0 +1,141 @@
+use crate::{
+    odds::{OddsFile, OddsTable, OddsTables},
+    Error,
+};
+
+/// The name of the treasure that uses the built-in odds tables
+pub const DEFAULT_TREASURE: &str = "standard";
+
+/// A treasure and the odds tables of each rarity it can drop
+#[derive(Clone, Debug)]
+pub struct Treasure {
+    pub name: String,
+    pub description: String,
+    pub tables: OddsTables,
+}
+
+impl Treasure {
+    /// The odds table for a rarity this treasure can drop
+    pub fn table(&self, rarity: &str) -> Result<&OddsTable, Error> {
+        self.tables.get(rarity).ok_or_else(|| Error::UnknownRarity {
+            treasure: self.name.clone(),
+            rarity: rarity.to_owned(),
+            known: self.tables.names().map(str::to_owned).collect(),
+        })
+    }
+}
+
+/// Every treasure the calculator knows about, looked up by name
+#[derive(Clone, Debug)]
+pub struct Treasures {
+    treasures: Vec<Treasure>,
+}
+
+impl Treasures {
+    /// The treasures that ship with the calculator. That's only the default treasure with the built-in odds tables;
+    /// every other treasure comes from an odds file.
+    pub fn builtin() -> Self {
+        Treasures {
+            treasures: vec![Treasure {
+                name: DEFAULT_TREASURE.to_owned(),
+                description: "The built-in rare, very rare and ultra rare odds".to_owned(),
+                tables: OddsTables::builtin(),
+            }],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Treasure> {
+        self.treasures.iter().find(|treasure| treasure.name == name)
+    }
+
+    /// Like `get`, but with an error listing the known treasures if there's no treasure called `name`
+    pub fn treasure(&self, name: &str) -> Result<&Treasure, Error> {
+        self.get(name).ok_or_else(|| Error::UnknownTreasure {
+            treasure: name.to_owned(),
+            known: self.names().map(str::to_owned).collect(),
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Treasure> {
+        self.treasures.iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.treasures.iter().map(|treasure| treasure.name.as_str())
+    }
+
+    /// Register the tables from an odds file. Tables are added to the treasure the file names (creating it if it
+    /// doesn't exist yet), or to the default treasure if it doesn't name one.
+    pub fn add_file(&mut self, file: OddsFile) {
+        let name = file.treasure.as_deref().unwrap_or(DEFAULT_TREASURE);
+        let treasure = match self.treasures.iter().position(|t| t.name == name) {
+            Some(i) => &mut self.treasures[i],
+            None => {
+                self.treasures.push(Treasure {
+                    name: name.to_owned(),
+                    description: String::new(),
+                    tables: OddsTables::default(),
+                });
+                self.treasures.last_mut().unwrap()
+            }
+        };
+
+        if let Some(description) = file.description {
+            treasure.description = description;
+        }
+        treasure.tables.extend(file.tables);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tail;
+
+    fn file(treasure: Option<&str>, rarity: &str) -> OddsFile {
+        OddsFile {
+            treasure: treasure.map(str::to_owned),
+            description: Some(format!("{rarity} odds")),
+            tables: vec![OddsTable {
+                name: rarity.to_owned(),
+                odds: vec![4., 2.],
+                tail: Tail::RepeatLast,
+            }],
+        }
+    }
+
+    #[test]
+    fn only_the_default_treasure_is_built_in() {
+        let treasures = Treasures::builtin();
+        assert_eq!(treasures.names().collect::<Vec<_>>(), [DEFAULT_TREASURE]);
+        let rarities: Vec<_> = treasures
+            .treasure(DEFAULT_TREASURE)
+            .unwrap()
+            .tables
+            .names()
+            .collect();
+        assert_eq!(rarities, ["rare", "very-rare", "ultra-rare"]);
+        assert!(treasures.treasure("collectors-cache").is_err());
+    }
+
+    #[test]
+    fn odds_files_add_treasures() {
+        let mut treasures = Treasures::builtin();
+        treasures.add_file(file(Some("cache"), "arcana"));
+        treasures.add_file(file(None, "mythical"));
+        assert_eq!(
+            treasures.names().collect::<Vec<_>>(),
+            [DEFAULT_TREASURE, "cache"]
+        );
+
+        let cache = treasures.treasure("cache").unwrap();
+        assert_eq!(cache.description, "arcana odds");
+        assert!(cache.table("arcana").is_ok());
+        assert!(cache.table("rare").is_err());
+        assert!(treasures
+            .treasure(DEFAULT_TREASURE)
+            .unwrap()
+            .table("mythical")
+            .is_ok());
+    }
+}
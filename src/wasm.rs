@@ -0,0 +1,88 @@
+//! Bindings for calling the calculator from JavaScript when it's built for WebAssembly. They use the built-in odds
+//! tables, and throw the calculator's error messages for invalid inputs.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    calc::{self, first_drop_probabilities},
+    chart::{self, ChartSpec},
+    plot::{LinePlot, Series},
+    treasure::{Treasures, DEFAULT_TREASURE},
+    OddsTable,
+};
+
+fn table(rarity: &str) -> Result<OddsTable, JsError> {
+    Ok(Treasures::builtin()
+        .treasure(DEFAULT_TREASURE)?
+        .table(rarity)?
+        .clone())
+}
+
+/// The names of the rarities that can be calculated with
+#[wasm_bindgen]
+pub fn rarities() -> Vec<String> {
+    Treasures::builtin()
+        .treasure(DEFAULT_TREASURE)
+        .map(|treasure| treasure.tables.names().map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+/// The expected number of boxes, starting with `treasure_opening`, needed to get an item of `rarity`
+#[wasm_bindgen(js_name = expectedValue)]
+pub fn expected_value(rarity: &str, treasure_opening: usize) -> Result<f64, JsError> {
+    Ok(calc::expected_value(&table(rarity)?, treasure_opening)?)
+}
+
+/// The probability of getting an item of `rarity` within `boxes` boxes, starting with `treasure_opening`
+#[wasm_bindgen]
+pub fn probability(rarity: &str, treasure_opening: usize, boxes: usize) -> Result<f64, JsError> {
+    Ok(calc::probability(&table(rarity)?, treasure_opening, boxes)?)
+}
+
+/// The probability of getting an item of `rarity` within each number of boxes from 0 to `max_boxes`, starting with
+/// `treasure_opening`
+#[wasm_bindgen]
+pub fn cdf(rarity: &str, treasure_opening: usize, max_boxes: usize) -> Result<Vec<f64>, JsError> {
+    let table = table(rarity)?;
+    let by = first_drop_probabilities::<f64>(&table, treasure_opening)?
+        .take(max_boxes)
+        .scan(0., |by, exactly| {
+            *by += exactly;
+            Some(*by)
+        });
+    Ok(std::iter::once(0.).chain(by).collect())
+}
+
+/// `cdf` plotted as an SVG image
+#[wasm_bindgen(js_name = cdfSvg)]
+pub fn cdf_svg(rarity: &str, treasure_opening: usize, max_boxes: usize) -> Result<String, JsError> {
+    let points = cdf(rarity, treasure_opening, max_boxes)?
+        .into_iter()
+        .enumerate()
+        .map(|(boxes, by)| (boxes as f64, by))
+        .collect();
+    Ok(LinePlot {
+        title: format!("{rarity} from treasure opening {treasure_opening}"),
+        x_label: "Boxes opened".to_owned(),
+        y_label: "Probability".to_owned(),
+        series: vec![Series {
+            name: format!("opening {treasure_opening}"),
+            points,
+        }],
+        y_range: Some((0., 1.)),
+    }
+    .to_svg())
+}
+
+/// Chart every starting treasure from 1 to `max_treasures` against every number of boxes from 1 to `max_boxes`, as
+/// an object with the same fields as a JSON chart file
+#[wasm_bindgen]
+pub fn chart(rarity: &str, max_treasures: usize, max_boxes: usize) -> Result<JsValue, JsError> {
+    let spec = ChartSpec::up_to(rarity, max_treasures, max_boxes);
+    let chart = chart::chart(
+        Treasures::builtin().treasure(DEFAULT_TREASURE)?,
+        &spec,
+        None,
+    )?;
+    serde_wasm_bindgen::to_value(&chart).map_err(|e| JsError::new(&e.to_string()))
+}
@@ -0,0 +1,167 @@
+//! Runs the command line tool on what it writes to files rather than prints: charts, exported odds files and the ledger of
+//! opened boxes
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A fresh directory to write charts and the ledger to
+fn dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("dota-odds-calc-cli-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run the calculator with a ledger in `dir`, returning its standard output, or its standard error if it fails
+fn run(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_dota-odds-calc"))
+        .arg("--ledger")
+        .arg(dir.join("ledger.json"))
+        .args(args)
+        .output()
+        .unwrap();
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => Err(String::from_utf8_lossy(&output.stderr).into_owned()),
+    }
+}
+
+#[test]
+fn charts_of_several_targets_are_one_grid_per_goal() {
+    let dir = dir("multi");
+    let out = dir.join("multi.csv");
+    let out_arg = out.to_str().unwrap();
+    let args = ["chart", "boxes=1..3", "opening=1..2", out_arg];
+    run(
+        &dir,
+        &[
+            &args[..],
+            &["--target", "rare:1", "--target", "ultra-rare:3"],
+        ]
+        .concat(),
+    )
+    .unwrap();
+
+    let records: Vec<_> = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_path(&out)
+        .unwrap()
+        .records()
+        .map(Result::unwrap)
+        .collect();
+    // A header, an expected value row and a row for each number of boxes per goal, with a blank record between
+    assert_eq!(records.len(), 11);
+    assert!(records
+        .iter()
+        .all(|record| record.len() == records[0].len()));
+    assert_eq!(&records[0][0], "all");
+    assert!(records[5].iter().all(str::is_empty));
+    assert_eq!(&records[6][0], "any");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn huge_ranges_are_errors() {
+    let dir = dir("ranges");
+    let out = dir.join("chart.csv");
+    let out = out.to_str().unwrap();
+    for (rows, columns) in [
+        ("opening=1..1e13", "boxes=1..2"),
+        ("opening=1..3", "boxes=1..2:log1000000000000"),
+        ("opening=1..3", "boxes=1..1e9:1e8"),
+    ] {
+        let error = run(&dir, &["rare", "chart", rows, columns, out]).unwrap_err();
+        assert!(error.contains("Invalid range"), "{error}");
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn treasures_recorded_with_odds_files_are_skipped_without_them() {
+    let dir = dir("ledger");
+    let odds = dir.join("odds.toml");
+    fs::write(
+        &odds,
+        "treasure = \"cache\"\n\n[[table]]\nname = \"arcana\"\nodds = [4, 2]\n",
+    )
+    .unwrap();
+    let odds = odds.to_str().unwrap();
+    let status = run(
+        &dir,
+        &["--odds-file", odds, "--treasure", "cache", "record", "open"],
+    )
+    .unwrap();
+    assert!(status.contains("cache (1 box recorded)"), "{status}");
+    assert!(status.contains("arcana: treasure opening 2"), "{status}");
+
+    let status = run(&dir, &["status"]).unwrap();
+    assert!(!status.contains("cache"), "{status}");
+    run(&dir, &["record", "open"]).unwrap();
+    let status = run(&dir, &["record", "open", "--got", "rare"]).unwrap();
+    assert!(status.contains("standard (2 boxes recorded)"), "{status}");
+    assert!(status.contains("rare: treasure opening 1,"), "{status}");
+    assert!(
+        status.contains("ultra-rare: treasure opening 3,"),
+        "{status}"
+    );
+
+    // Expected boxes are rounded to the precision asked for
+    let expected = |status: &str| -> Vec<f64> {
+        status
+            .lines()
+            .filter_map(|line| line.strip_suffix(" more boxes expected"))
+            .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+            .collect()
+    };
+    let full = expected(&run(&dir, &["status"]).unwrap());
+    let rounded = expected(&run(&dir, &["status", "--precision", "3"]).unwrap());
+    assert_eq!(full.len(), 3);
+    for (full, rounded) in full.iter().zip(&rounded) {
+        assert_eq!(format!("{full:.2e}").parse::<f64>().unwrap(), *rounded);
+        assert_ne!(full, rounded);
+    }
+
+    // The opening recorded in the ledger is used when none is given
+    let from_ledger = run(&dir, &["ultra-rare", "expected-value"]).unwrap();
+    assert_eq!(
+        from_ledger,
+        run(&dir, &["ultra-rare", "3", "expected-value"]).unwrap()
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn broken_ledgers_only_matter_when_they_are_needed() {
+    let dir = dir("broken-ledger");
+    fs::write(dir.join("ledger.json"), "not json").unwrap();
+    run(&dir, &["rare", "5", "expected-value"]).unwrap();
+    for args in [&["rare", "expected-value"][..], &["status"]] {
+        let error = run(&dir, args).unwrap_err();
+        assert!(error.contains("Could not use ledger"), "{error}");
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn exports_that_cant_be_written_say_so() {
+    let dir = dir("export");
+    let log = dir.join("openings.csv");
+    fs::write(&log, "opening,result\n1,none\n2,rare\n").unwrap();
+    let export = dir.join("missing").join("rare-fitted.toml");
+    let error = run(
+        &dir,
+        &[
+            "rare",
+            "estimate",
+            log.to_str().unwrap(),
+            "--export",
+            export.to_str().unwrap(),
+        ],
+    )
+    .unwrap_err();
+    assert!(error.contains("Could not write odds file"), "{error}");
+    assert!(error.contains("rare-fitted.toml"), "{error}");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,134 @@
+use std::{
+    env,
+    ffi::{CStr, CString},
+    fs, ptr,
+    sync::{Mutex, MutexGuard},
+};
+
+use dota_odds_calc::{
+    expected_value,
+    ffi::{
+        doc_expected_value, doc_last_error, doc_load_odds_file, doc_probability, doc_reset_odds,
+        doc_set_treasure, DocStatus,
+    },
+    probability, Rarity,
+};
+
+/// The odds tables are shared by every thread, so tests that change them can't run at the same time as the others
+static ODDS: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    ODDS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+fn last_error() -> Option<String> {
+    let message = doc_last_error();
+    (!message.is_null()).then(|| {
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_owned()
+    })
+}
+
+fn ev(rarity: &str, treasure_opening: usize) -> (DocStatus, f64) {
+    let mut out = f64::NAN;
+    let status = unsafe { doc_expected_value(c(rarity).as_ptr(), treasure_opening, &mut out) };
+    (status, out)
+}
+
+#[test]
+fn results_are_written_only_on_success() {
+    let _odds = lock();
+    let expected: f64 = expected_value(&Rarity::UltraRare.table(), 12).unwrap();
+    assert_eq!(ev("ultra-rare", 12), (DocStatus::Ok, expected));
+    assert_eq!(last_error(), None);
+
+    let mut out = -1.;
+    let status = unsafe { doc_probability(c("rare").as_ptr(), 3, 10, &mut out) };
+    let expected: f64 = probability(&Rarity::Rare.table(), 3, 10).unwrap();
+    assert_eq!((status, out), (DocStatus::Ok, expected));
+    let before = out;
+    let status = unsafe { doc_probability(c("rare").as_ptr(), 0, 10, &mut out) };
+    assert_eq!(status, DocStatus::InvalidTreasureOpening);
+    assert_eq!(out, before);
+}
+
+#[test]
+fn invalid_inputs_are_reported() {
+    let _odds = lock();
+    assert_eq!(ev("rare", 0).0, DocStatus::InvalidTreasureOpening);
+    assert!(last_error().unwrap().contains('0'));
+    assert_eq!(ev("mythical", 1).0, DocStatus::UnknownRarity);
+    assert!(last_error().unwrap().contains("mythical"));
+
+    // A successful call clears the last error
+    assert_eq!(ev("rare", 1).0, DocStatus::Ok);
+    assert_eq!(last_error(), None);
+}
+
+#[test]
+fn null_pointers_are_reported() {
+    let _odds = lock();
+    let mut out = 0.;
+    let status = unsafe { doc_expected_value(ptr::null(), 1, &mut out) };
+    assert_eq!(status, DocStatus::NullPointer);
+    assert!(last_error().unwrap().contains("rarity"));
+    let status = unsafe { doc_probability(c("rare").as_ptr(), 1, 10, ptr::null_mut()) };
+    assert_eq!(status, DocStatus::NullPointer);
+    assert_eq!(
+        unsafe { doc_load_odds_file(ptr::null()) },
+        DocStatus::NullPointer
+    );
+}
+
+#[test]
+fn invalid_utf8_is_reported() {
+    let _odds = lock();
+    let rarity = CString::new([0xff, 0xfe]).unwrap();
+    let mut out = 0.;
+    let status = unsafe { doc_expected_value(rarity.as_ptr(), 1, &mut out) };
+    assert_eq!(status, DocStatus::InvalidUtf8);
+}
+
+#[test]
+fn odds_files_can_be_loaded_and_reset() {
+    let _odds = lock();
+    let dir = env::temp_dir().join(format!("dota-odds-calc-ffi-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("odds.toml");
+    fs::write(
+        &path,
+        "treasure = \"cache\"\n\n[[table]]\nname = \"arcana\"\nodds = [4, 2]\n",
+    )
+    .unwrap();
+
+    let missing = c(dir.join("missing.toml").to_str().unwrap());
+    assert_eq!(
+        unsafe { doc_load_odds_file(missing.as_ptr()) },
+        DocStatus::Io
+    );
+    assert_eq!(
+        unsafe { doc_set_treasure(c("cache").as_ptr()) },
+        DocStatus::UnknownTreasure
+    );
+
+    let path = c(path.to_str().unwrap());
+    assert_eq!(unsafe { doc_load_odds_file(path.as_ptr()) }, DocStatus::Ok);
+    assert_eq!(
+        unsafe { doc_set_treasure(c("cache").as_ptr()) },
+        DocStatus::Ok
+    );
+    // 1 in 4, then 1 in 2 forever: 1 + 3/4 * 2
+    assert_eq!(ev("arcana", 1), (DocStatus::Ok, 2.5));
+    assert_eq!(ev("rare", 1).0, DocStatus::UnknownRarity);
+
+    assert_eq!(doc_reset_odds(), DocStatus::Ok);
+    assert_eq!(ev("arcana", 1).0, DocStatus::UnknownRarity);
+    assert_eq!(ev("rare", 1).0, DocStatus::Ok);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,134 @@
+//! Runs Python snippets against the extension module in an embedded interpreter, so it needs a Python to link to (the
+//! one maturin would build for, or `PYO3_PYTHON`)
+
+use std::ffi::CString;
+
+use dota_odds_calc::{calc, python, Rarity};
+use pyo3::{prelude::*, types::PyDict, wrap_pymodule};
+
+/// Run `code` with the module imported as `doc`, panicking with the Python traceback if it raises
+fn run(code: &str) {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals
+            .set_item("doc", wrap_pymodule!(python::dota_odds_calc)(py))
+            .unwrap();
+        if let Err(e) = py.run(&CString::new(code).unwrap(), Some(&globals), None) {
+            e.display(py);
+            panic!("{e}");
+        }
+    });
+}
+
+#[test]
+fn rarity_is_a_python_enum() {
+    run(r#"
+assert doc.Rarity.all() == [doc.Rarity.RARE, doc.Rarity.VERY_RARE, doc.Rarity.ULTRA_RARE]
+assert doc.Rarity.from_name("very-rare") == doc.Rarity.VERY_RARE
+assert doc.Rarity.ULTRA_RARE.name == "ultra-rare"
+assert str(doc.Rarity.RARE) == "rare"
+assert doc.Rarity.RARE.odds[:2] == [20000.0, 583.0]
+assert doc.Rarity.RARE.table().name == "rare"
+"#);
+}
+
+#[test]
+fn rarities_can_be_names_enums_or_tables() {
+    let expected: f64 = calc::expected_value(&Rarity::UltraRare.table(), 12).unwrap();
+    run(&format!(
+        r#"
+expected = doc.expected_value("ultra-rare", 12)
+assert isinstance(expected, float)
+assert expected == {expected:?}
+assert doc.expected_value(doc.Rarity.ULTRA_RARE, 12) == expected
+assert doc.expected_value(doc.Rarity.ULTRA_RARE.table(), 12) == expected
+"#
+    ));
+}
+
+#[test]
+fn functions_are_vectorised() {
+    let expected: Vec<f64> = (1..=3)
+        .map(|boxes| calc::probability(&Rarity::Rare.table(), 5, boxes).unwrap())
+        .collect();
+    run(&format!(
+        r#"
+import array
+by = doc.probability("rare", 5, [1, 2, 3])
+assert isinstance(by, array.array) and memoryview(by).format == "d"
+assert list(by) == [doc.probability("rare", 5, boxes) for boxes in [1, 2, 3]]
+assert all(abs(a - b) < 1e-12 for a, b in zip(by, {expected:?}))
+assert list(doc.probability("rare", range(5, 8), 1)) == [doc.probability("rare", opening, 1) for opening in range(5, 8)]
+assert list(doc.probability("rare", [5, 6], [1, 2])) == [doc.probability("rare", 5, 1), doc.probability("rare", 6, 2)]
+assert list(doc.expected_value("rare", [1, 2])) == [doc.expected_value("rare", 1), doc.expected_value("rare", 2)]
+assert len(doc.expected_value("rare", [])) == 0
+"#
+    ));
+}
+
+#[test]
+fn distribution_and_cdf_are_indexed_by_boxes() {
+    let points = calc::distribution::<f64>(&Rarity::VeryRare.table(), 1, 50).unwrap();
+    let (exactly, by): (Vec<f64>, Vec<f64>) =
+        points.iter().map(|point| (point.exactly, point.by)).unzip();
+    run(&format!(
+        r#"
+exactly = doc.distribution("very-rare", 1, 50)
+by = doc.cdf("very-rare", 1, 50)
+assert len(exactly) == len(by) == 51
+assert exactly[0] == by[0] == 0
+assert abs(sum(exactly) - by[50]) < 1e-12
+assert by[50] == doc.probability("very-rare", 1, 50)
+assert all(a <= b for a, b in zip(by, by[1:]))
+assert list(exactly[1:]) == {exactly:?}
+assert list(by[1:]) == {by:?}
+"#
+    ));
+}
+
+#[test]
+fn odds_tables_can_be_made_and_loaded() {
+    let dir = std::env::temp_dir().join(format!("dota-odds-calc-python-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("odds.toml");
+    std::fs::write(
+        &path,
+        "[[table]]\nname = \"arcana\"\nodds = [4, 2]\ntail = 3\n",
+    )
+    .unwrap();
+    run(&format!(
+        r#"
+table = doc.OddsTable("arcana", [4, 2], tail=3)
+assert (table.name, table.odds, table.tail) == ("arcana", [4.0, 2.0], 3.0)
+assert repr(table) == "OddsTable('arcana', [4.0, 2.0], tail=3.0)"
+assert doc.load_odds_file({path:?}) == [table]
+# 1 in 4, then 1 in 2, then 1 in 3 forever: 1 + 3/4 * (1 + 1/2 * 3)
+assert doc.expected_value(table, 1) == 2.875
+"#
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid_inputs_raise() {
+    run(r#"
+def raises(error, f, *args):
+    try:
+        f(*args)
+    except error as e:
+        return str(e)
+    raise AssertionError(f"{f.__name__}{args} didn't raise {error.__name__}")
+
+assert "opening" in raises(ValueError, doc.expected_value, "rare", 0)
+assert "mythical" in raises(ValueError, doc.probability, "mythical", 1, 1)
+raises(ValueError, doc.probability, "rare", [1, 2], [1, 2, 3])
+raises(ValueError, doc.OddsTable, "broken", [])
+raises(ValueError, doc.Rarity.from_name, "mythical")
+raises(FileNotFoundError, doc.load_odds_file, "/nonexistent/odds.toml")
+assert "negative" in raises(ValueError, doc.expected_value, "rare", -1)
+raises(ValueError, doc.probability, "rare", 1, [1, -1])
+raises(ValueError, doc.distribution, "rare", 1, -1)
+raises(ValueError, doc.cdf, "rare", -1, 10)
+"#);
+}
@@ -0,0 +1,194 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+};
+
+use dota_odds_calc::{expected_value, probability, server::Server, treasure::Treasures, Rarity};
+use serde_json::Value;
+
+/// Start a server on a free port in the background
+fn start() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0", Treasures::builtin()).unwrap();
+    let addr = server.addr();
+    thread::spawn(move || server.run());
+    addr
+}
+
+/// Send a request and return the status code and JSON body of the response
+fn request(addr: SocketAddr, method: &str, target: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {target} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn get(addr: SocketAddr, target: &str) -> (u16, Value) {
+    request(addr, "GET", target, "")
+}
+
+#[test]
+fn parameters_from_query_are_echoed() {
+    let addr = start();
+    let (status, json) = get(
+        addr,
+        "/expected-value?rarity=ultra-rare&treasure_opening=12",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(json["treasure"], "standard");
+    assert_eq!(json["rarity"], "ultra-rare");
+    assert_eq!(json["treasure_opening"], 12);
+    let expected: f64 = expected_value(&Rarity::UltraRare.table(), 12).unwrap();
+    assert!((json["expected_value"].as_f64().unwrap() - expected).abs() < 1e-12);
+}
+
+#[test]
+fn treasure_opening_defaults_to_1() {
+    let addr = start();
+    let (status, json) = get(addr, "/expected-value?rarity=rare");
+    assert_eq!(status, 200);
+    assert_eq!(json["treasure_opening"], 1);
+}
+
+#[test]
+fn parameters_from_json_body_are_echoed() {
+    let addr = start();
+    let (status, json) = request(
+        addr,
+        "POST",
+        "/probability",
+        r#"{"rarity": "very-rare", "treasure_opening": 5, "boxes": 30}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(json["rarity"], "very-rare");
+    assert_eq!(json["treasure_opening"], 5);
+    assert_eq!(json["boxes"], 30);
+    let expected: f64 = probability(&Rarity::VeryRare.table(), 5, 30).unwrap();
+    assert!((json["probability"].as_f64().unwrap() - expected).abs() < 1e-12);
+}
+
+#[test]
+fn chart_is_the_json_chart_format() {
+    let addr = start();
+    let (status, json) = get(addr, "/chart?rarity=rare&max_treasures=3&max_boxes=4");
+    assert_eq!(status, 200);
+    assert_eq!(json["measure"], "probability");
+    let boxes: Vec<_> = json["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|column| column["value"].clone())
+        .collect();
+    assert_eq!(boxes, [1, 2, 3, 4]);
+    let rows = json["rows"].as_array().unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2]["value"], 3);
+    assert_eq!(json["cells"][2].as_array().unwrap().len(), 4);
+    let expected: f64 = probability(&Rarity::Rare.table(), 3, 4).unwrap();
+    assert!((json["cells"][2][3].as_f64().unwrap() - expected).abs() < 1e-12);
+}
+
+#[test]
+fn chart_can_sweep_ranges() {
+    let addr = start();
+    let (status, json) = get(
+        addr,
+        "/chart?rarity=ultra-rare&rows=opening=30..50&columns=boxes=10..200:10",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(json["rows"].as_array().unwrap().len(), 21);
+    assert_eq!(json["columns"].as_array().unwrap().len(), 20);
+    assert_eq!(json["rows"][0]["value"], 30);
+    assert_eq!(json["columns"][19]["value"], 200);
+    // Row 31 against column 20
+    let expected: f64 = probability(&Rarity::UltraRare.table(), 31, 20).unwrap();
+    assert!((json["cells"][1][1].as_f64().unwrap() - expected).abs() < 1e-12);
+}
+
+#[test]
+fn opening_0_is_a_bad_request() {
+    let addr = start();
+    for target in [
+        "/expected-value?rarity=rare&treasure_opening=0",
+        "/probability?rarity=rare&treasure_opening=0&boxes=10",
+    ] {
+        let (status, json) = get(addr, target);
+        assert_eq!(status, 400, "{target}");
+        assert!(json["error"].as_str().unwrap().contains("Treasure opening"));
+    }
+}
+
+#[test]
+fn unknown_rarity_is_not_found() {
+    let addr = start();
+    let (status, json) = get(addr, "/probability?rarity=mythical&boxes=10");
+    assert_eq!(status, 404);
+    assert!(json["error"].as_str().unwrap().contains("mythical"));
+}
+
+#[test]
+fn unknown_treasure_is_not_found() {
+    let addr = start();
+    let (status, _) = get(addr, "/expected-value?treasure=nope&rarity=rare");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn missing_and_invalid_parameters_are_bad_requests() {
+    let addr = start();
+    for target in [
+        "/expected-value",
+        "/probability?rarity=rare",
+        "/probability?rarity=rare&boxes=lots",
+        "/probability?rarity=rare&boxes=10000000000",
+        "/expected-value?rarity=rare&treasure_opening=10000000000",
+        "/chart?rarity=rare&max_boxes=10",
+        "/chart?rarity=rare&max_treasures=1000&max_boxes=1000",
+        "/chart?rarity=rare&rows=opening=5..1&columns=boxes=1..10",
+        "/chart?rarity=rare&rows=boxes=1..10&columns=boxes=1..10",
+    ] {
+        let (status, json) = get(addr, target);
+        assert_eq!(status, 400, "{target}");
+        assert!(json["error"].is_string());
+    }
+
+    let (status, _) = request(addr, "POST", "/probability", "not json");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn huge_charts_are_bad_requests() {
+    let addr = start();
+    for target in [
+        "/chart?rarity=rare&rows=opening=1..1e13&columns=boxes=1..2",
+        "/chart?rarity=rare&rows=opening=1..3&columns=boxes=1..2:log1000000000000",
+        "/chart?rarity=rare&rows=opening=1..3&columns=boxes=1..1e9:1e8",
+        "/chart?rarity=rare&rows=opening=1..100&columns=boxes=99000..100000:1000",
+    ] {
+        let (status, json) = get(addr, target);
+        assert_eq!(status, 400, "{target}");
+        assert!(json["error"].is_string());
+    }
+    // The server is still up and answering
+    let (status, _) = get(addr, "/expected-value?rarity=rare");
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn unknown_endpoints_and_methods() {
+    let addr = start();
+    let (status, _) = get(addr, "/quantile?rarity=rare");
+    assert_eq!(status, 404);
+    let (status, _) = request(addr, "DELETE", "/expected-value?rarity=rare", "");
+    assert_eq!(status, 405);
+}
@@ -0,0 +1,74 @@
+//! Run with `wasm-pack test --node -- --no-default-features --features wasm`, or `--headless --firefox` (or
+//! `--chrome`) instead of `--node` to run in a headless browser
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use dota_odds_calc::{
+    calc,
+    treasure::{Treasures, DEFAULT_TREASURE},
+    wasm, Rarity,
+};
+use serde_json::Value;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn every_rarity_listed_can_be_calculated() {
+    let rarities = wasm::rarities();
+    assert!(!rarities.is_empty());
+    let treasures = Treasures::builtin();
+    let standard = treasures.treasure(DEFAULT_TREASURE).unwrap();
+    for rarity in rarities {
+        let table = standard.table(&rarity).unwrap();
+        let expected: f64 = calc::expected_value(table, 12).unwrap();
+        assert_eq!(
+            wasm::expected_value(&rarity, 12).unwrap(),
+            expected,
+            "{rarity}"
+        );
+        let expected: f64 = calc::probability(table, 3, 10).unwrap();
+        assert_eq!(
+            wasm::probability(&rarity, 3, 10).unwrap(),
+            expected,
+            "{rarity}"
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+fn invalid_inputs_are_errors() {
+    assert!(wasm::expected_value("mythical", 1).is_err());
+    assert!(wasm::probability("rare", 0, 10).is_err());
+    assert!(wasm::cdf("rare", 0, 10).is_err());
+    assert!(wasm::cdf_svg("mythical", 1, 10).is_err());
+    assert!(wasm::chart("mythical", 3, 4).is_err());
+}
+
+#[wasm_bindgen_test]
+fn cdf_is_indexed_by_boxes() {
+    let cdf = wasm::cdf("very-rare", 1, 50).unwrap();
+    assert_eq!(cdf.len(), 51);
+    assert_eq!(cdf[0], 0.);
+    assert!(cdf.windows(2).all(|pair| pair[0] <= pair[1]));
+    let table = Rarity::VeryRare.table();
+    for boxes in [1, 10, 50] {
+        let expected: f64 = calc::probability(&table, 1, boxes).unwrap();
+        assert!((cdf[boxes] - expected).abs() < 1e-12);
+    }
+}
+
+#[wasm_bindgen_test]
+fn cdf_svg_is_an_svg() {
+    let svg = wasm::cdf_svg("rare", 1, 20).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("rare from treasure opening 1"));
+}
+
+#[wasm_bindgen_test]
+fn chart_is_an_object_like_a_json_chart() {
+    let chart: Value = serde_wasm_bindgen::from_value(wasm::chart("rare", 3, 4).unwrap()).unwrap();
+    assert_eq!(chart["rows"].as_array().unwrap().len(), 3);
+    assert_eq!(chart["columns"].as_array().unwrap().len(), 4);
+    assert_eq!(chart["cells"][2].as_array().unwrap().len(), 4);
+    let expected: f64 = calc::probability(&Rarity::Rare.table(), 3, 4).unwrap();
+    assert_eq!(chart["cells"][2][3].as_f64().unwrap(), expected);
+}